@@ -0,0 +1,82 @@
+//! Benchmark of the k-d tree against a linear scan, on the real trace
+//!
+//! Run with `cargo bench --bench spatial`
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use jour_09::densify::densify;
+use jour_09::geo::{Distance, GeoPoint, TracePoint};
+use jour_09::parse_and_sort;
+use jour_09::reader::ReaderConfig;
+use jour_09::spatial::{SpatialIndex, linear_scan};
+use jour_09::validation::ValidationPolicy;
+
+/// The trace.txt shipped with the puzzle, sorted and validated
+fn real_trace() -> Vec<TracePoint> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/trace.txt");
+    let (trace, _) = parse_and_sort(path, &ReaderConfig::default(), &ValidationPolicy::default())
+        .expect("trace.txt is readable");
+    trace
+}
+
+/// The real trace with great-circle points every `step`, renumbered
+fn densified(trace: &[TracePoint], step: &str) -> Vec<TracePoint> {
+    densify(trace, step.parse().unwrap())
+        .unwrap()
+        .into_iter()
+        .enumerate()
+        .map(|(index, point)| TracePoint {
+            id: index as i32 + 1,
+            position: point.position,
+        })
+        .collect()
+}
+
+/// Queries the way the CLI is used: around places the sleigh flew over
+fn queries(trace: &[TracePoint]) -> Vec<GeoPoint> {
+    trace.iter().step_by(25).map(|point| point.geo()).collect()
+}
+
+fn bench_spatial_index(c: &mut Criterion) {
+    let trace = real_trace();
+    let radius = Distance::from_km(50.0);
+
+    let mut group = c.benchmark_group("nearest_10_and_within_50km");
+    for (name, points) in [
+        ("trace.txt", trace.clone()),
+        ("densified_10km", densified(&trace, "10km")),
+        ("densified_1km", densified(&trace, "1km")),
+    ] {
+        let queries = queries(&points);
+        let label = format!("{} ({} points)", name, points.len());
+
+        group.bench_with_input(BenchmarkId::new("index", &label), &points, |b, points| {
+            b.iter(|| {
+                // The index is built once per run, as the CLI does
+                let index = SpatialIndex::build(points);
+                for &origin in &queries {
+                    black_box(index.nearest(origin, 10));
+                    black_box(index.within_radius(origin, radius));
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("linear_scan", &label),
+            &points,
+            |b, points| {
+                b.iter(|| {
+                    for &origin in &queries {
+                        let scan = linear_scan(points, origin);
+                        black_box(scan.iter().take(10).count());
+                        black_box(scan.iter().filter(|n| n.distance <= radius).count());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_spatial_index);
+criterion_main!(benches);
@@ -0,0 +1,329 @@
+use clap::{Parser, Subcommand};
+
+use crate::extsort::DEFAULT_RUN_SIZE;
+use crate::geo::{Distance, DomainPolicy, Unit};
+use crate::output::{MAX_PRECISION, Printer};
+use crate::reader::ReaderConfig;
+use crate::svg::{self, Projection};
+use crate::validation::{DuplicatePolicy, GapPolicy, ValidationPolicy};
+
+/// Command-line interface structure
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Distance covered by the sleigh",
+    long_about = "Computes the distance between the first and last point of the sleigh trace, \
+                  and other analyses of the trace (see the subcommands)."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Trace file to read, or "-" for the standard input
+    #[arg(short, long, global = true, default_value = "trace.txt")]
+    pub input: String,
+
+    /// Unit of the reported distances
+    #[arg(long, global = true, value_enum, default_value_t = Unit::Km)]
+    pub unit: Unit,
+
+    /// Decimals of the reported distances (at most 15)
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u8).range(..=i64::from(MAX_PRECISION))
+    )]
+    pub precision: u8,
+
+    /// Print the report as a JSON document
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// The first line holds column names (id, x, y, optional sleigh_id, extra columns are ignored)
+    #[arg(long, global = true)]
+    pub header: bool,
+
+    /// Field separator
+    #[arg(long, global = true, default_value_t = ',')]
+    pub delimiter: char,
+
+    /// Strip surrounding whitespace from every field
+    #[arg(long, global = true)]
+    pub trim: bool,
+
+    /// Ignore lines starting with this character
+    #[arg(long, global = true)]
+    pub comment: Option<char>,
+
+    /// Rows start with a sleigh id: sleigh_id,id,x,y
+    #[arg(long, global = true)]
+    pub fleet: bool,
+
+    /// How to handle ids appearing on several lines
+    #[arg(long, global = true, value_enum, default_value_t = DuplicatePolicy::Reject)]
+    pub duplicates: DuplicatePolicy,
+
+    /// How to handle ids missing from the sequence
+    #[arg(long, global = true, value_enum, default_value_t = GapPolicy::Keep)]
+    pub gaps: GapPolicy,
+
+    /// How to handle coordinates outside the Web Mercator square
+    /// (|x| or |y| above 20037508.34 m, i.e. beyond 180° or 85.05°)
+    #[arg(long, global = true, value_enum, default_value_t = DomainPolicy::Reject)]
+    pub out_of_domain: DomainPolicy,
+
+    /// Sort through temporary files instead of memory, for traces larger
+    /// than RAM (distance report only)
+    #[arg(long, global = true)]
+    pub external: bool,
+
+    /// Records sorted in memory per temporary file with --external
+    #[arg(long, global = true, default_value_t = DEFAULT_RUN_SIZE)]
+    pub run_size: usize,
+}
+
+/// Available analyses of the trace
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Prints the distance between the first and last point (default)
+    Distance,
+    /// Reports the distances of every sleigh of a fleet log (implies --fleet)
+    Fleet,
+    /// Measures how far an actual trace deviated from the planned route
+    Compare {
+        /// Trace of the planned route
+        planned: String,
+
+        /// Trace actually flown
+        actual: String,
+
+        /// Number of most divergent point pairs to list
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Lists bearings, turn angles and length of every segment
+    Segments {
+        /// Print the segments as CSV, without the summary
+        #[arg(long)]
+        csv: bool,
+
+        /// Number of sharpest turns to list in the summary
+        #[arg(long, default_value_t = 5)]
+        sharpest: usize,
+    },
+    /// Inserts great-circle points so that consecutive points are at most `step` apart
+    Densify {
+        /// Maximum distance between points, with a unit (m, km, mi, nmi)
+        #[arg(long)]
+        step: Distance,
+
+        /// Number the points 1, 2, 3... instead of giving inserted points
+        /// fractional ids (needed to read the output back)
+        #[arg(long)]
+        renumber: bool,
+
+        /// Path of the file to write (defaults to standard output)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Draws the trace as an SVG image
+    Svg {
+        /// Path of the SVG file to write
+        #[arg(short, long, default_value = "trace.svg")]
+        output: String,
+
+        /// Map projection
+        #[arg(long, value_enum, default_value_t = Projection::Mercator)]
+        projection: Projection,
+
+        /// Width of the image in pixels, margins included (more than 80)
+        #[arg(long, default_value_t = 800.0, value_parser = image_width)]
+        width: f64,
+
+        /// Write the id next to every point
+        #[arg(long)]
+        labels: bool,
+
+        /// Draw meridians and parallels
+        #[arg(long)]
+        graticule: bool,
+
+        /// Draw a distance scale bar
+        #[arg(long)]
+        scale_bar: bool,
+
+        /// Coastline file ("lon,lat" per line, polylines separated by blank lines)
+        #[arg(long)]
+        coastline: Option<String>,
+    },
+    /// Draws the trace in the terminal with braille characters
+    Plot {
+        /// Width of the plot in characters (defaults to the terminal width)
+        #[arg(long)]
+        columns: Option<usize>,
+
+        /// Height of the plot in lines (defaults to the terminal height)
+        #[arg(long)]
+        rows: Option<usize>,
+
+        /// Disable colors, start and end are then marked with '●'
+        #[arg(long)]
+        no_color: bool,
+    },
+    /// Reports when the trace enters and leaves restricted zones
+    Geofence {
+        /// GeoJSON file with the zones (Polygon or MultiPolygon features)
+        zones: String,
+    },
+    /// Names the places closest to the start and the end of the trace
+    Places {
+        /// Gazetteer file ("name,lat,lon" per line)
+        gazetteer: String,
+
+        /// Annotate every point, not only the start and the end
+        #[arg(long)]
+        every: bool,
+    },
+    /// Finds where the sleigh lingered (DBSCAN over great-circle distances)
+    Clusters {
+        /// Neighbourhood radius, with a unit (m, km, mi, nmi)
+        #[arg(long)]
+        eps: Distance,
+
+        /// Points within the radius (the point included) needed to start a cluster
+        #[arg(long, default_value_t = 3)]
+        min_points: usize,
+    },
+    /// Lists the trace points closest to a place
+    Nearest {
+        /// Latitude of the place in degrees
+        #[arg(long, allow_negative_numbers = true)]
+        lat: f64,
+
+        /// Longitude of the place in degrees
+        #[arg(long, allow_negative_numbers = true)]
+        lon: f64,
+
+        /// Number of points to list
+        #[arg(short, long, default_value_t = 1)]
+        k: usize,
+    },
+    /// Lists the trace points within a distance of a place
+    WithinRadius {
+        /// Latitude of the place in degrees
+        #[arg(long, allow_negative_numbers = true)]
+        lat: f64,
+
+        /// Longitude of the place in degrees
+        #[arg(long, allow_negative_numbers = true)]
+        lon: f64,
+
+        /// Search radius, with a unit (m, km, mi, nmi)
+        #[arg(long)]
+        radius: Distance,
+    },
+}
+
+/// Parses an SVG width, which must leave room for the drawing between the
+/// margins (the height follows from the width and the trace aspect ratio)
+fn image_width(raw: &str) -> Result<f64, String> {
+    let width: f64 = raw.parse().map_err(|err| format!("{}", err))?;
+    if width > 2.0 * svg::MARGIN && width.is_finite() {
+        Ok(width)
+    } else {
+        Err(format!(
+            "must be more than {} pixels (twice the margin)",
+            2.0 * svg::MARGIN
+        ))
+    }
+}
+
+impl Cli {
+    /// Builds the file layout from the command-line flags
+    pub fn reader_config(&self) -> ReaderConfig {
+        ReaderConfig {
+            delimiter: self.delimiter,
+            has_header: self.header,
+            trim: self.trim,
+            comment: self.comment,
+            fleet: self.fleet || matches!(self.command, Some(Commands::Fleet)),
+            domain: self.out_of_domain,
+        }
+    }
+
+    /// Builds the report printer from the output flags
+    pub fn printer(&self) -> Printer {
+        Printer {
+            unit: self.unit,
+            precision: usize::from(self.precision),
+            json: self.json,
+        }
+    }
+
+    /// Builds the validation policy from the command-line flags
+    pub fn policy(&self) -> ValidationPolicy {
+        ValidationPolicy {
+            duplicates: self.duplicates,
+            gaps: self.gaps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that SVG widths leaving no room inside the margins are refused
+    #[test]
+    fn test_svg_width() {
+        let width = |raw: &str| match Cli::try_parse_from(["jour-09", "svg", "--width", raw]) {
+            Ok(Cli {
+                command: Some(Commands::Svg { width, .. }),
+                ..
+            }) => Ok(width),
+            Ok(_) => unreachable!("svg subcommand"),
+            Err(err) => Err(err.to_string()),
+        };
+
+        assert_eq!(width("81"), Ok(81.0));
+        for raw in ["80", "12", "-5", "inf", "wide"] {
+            assert!(width(raw).is_err(), "{}", raw);
+        }
+        assert!(width("80").unwrap_err().contains("more than 80 pixels"));
+    }
+
+    /// Tests that the search radius takes a unit like the other distances
+    #[test]
+    fn test_within_radius_unit() {
+        let cli = Cli::try_parse_from([
+            "jour-09",
+            "within-radius",
+            "--lat",
+            "45",
+            "--lon",
+            "4",
+            "--radius",
+            "3mi",
+        ])
+        .unwrap();
+        let Some(Commands::WithinRadius { radius, .. }) = cli.command else {
+            unreachable!("within-radius subcommand")
+        };
+        assert_eq!(radius, Distance::from_meters(3.0 * 1609.344));
+    }
+
+    /// Tests that precisions beyond what an f64 holds are refused
+    #[test]
+    fn test_precision_bound() {
+        let precision = |raw: &str| {
+            Cli::try_parse_from(["jour-09", "--precision", raw]).map(|cli| cli.printer().precision)
+        };
+
+        assert_eq!(precision("0").unwrap(), 0);
+        assert_eq!(precision("15").unwrap(), 15);
+        for raw in ["16", "2000000000", "-1"] {
+            assert!(precision(raw).is_err(), "{}", raw);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::geo::{Distance, GeoPoint, TracePoint};
+use crate::spatial::SpatialIndex;
+
+/// A group of points where the sleigh lingered
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// Ids of the members, in increasing order
+    pub members: Vec<i32>,
+    /// Mean position of the members on the sphere
+    pub centroid: GeoPoint,
+}
+
+/// Clusters found in a trace and the points belonging to none of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    pub clusters: Vec<Cluster>,
+    pub noise: Vec<i32>,
+}
+
+impl Cluster {
+    /// Ids elapsed between the first and the last member
+    pub fn id_span(&self) -> i64 {
+        match (self.members.first(), self.members.last()) {
+            (Some(&first), Some(&last)) => i64::from(last) - i64::from(first),
+            _ => 0,
+        }
+    }
+}
+
+/// Spherical centroid: the normalized mean of the unit vectors
+///
+/// Unlike averaging latitudes and longitudes, this is right across the
+/// antimeridian and near the poles.
+fn centroid(points: &[GeoPoint]) -> GeoPoint {
+    let (x, y, z) = points.iter().fold((0.0, 0.0, 0.0), |(x, y, z), point| {
+        let (lat, lon) = (point.lat().to_radians(), point.lon().to_radians());
+        (
+            x + lat.cos() * lon.cos(),
+            y + lat.cos() * lon.sin(),
+            z + lat.sin(),
+        )
+    });
+
+    GeoPoint::new(z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+        .expect("atan2 results are finite and within range")
+}
+
+/// DBSCAN over the great-circle distances between the trace points
+///
+/// A point with at least `min_points` points (itself included) within `eps`
+/// is a core point; clusters are the core points reachable from one another
+/// through such neighbourhoods, plus their border points. Every other point
+/// is noise. Neighbourhoods are found with the spatial index, so the whole
+/// run takes about O(n log n) on sparse traces.
+///
+/// Clusters are numbered in trace order of their first core point.
+pub fn dbscan(trace: &[TracePoint], eps: Distance, min_points: usize) -> Clustering {
+    let geo: Vec<GeoPoint> = trace.iter().map(|point| point.geo()).collect();
+    // The index ids are positions in the trace, not trace ids
+    let index = SpatialIndex::from_positions(geo.iter().zip(0..).map(|(&point, i)| (i, point)));
+    let neighbours = |i: usize| -> Vec<usize> {
+        index
+            .within_radius(geo[i], eps)
+            .into_iter()
+            .map(|n| n.id as usize)
+            .collect()
+    };
+
+    let mut visited = vec![false; trace.len()];
+    let mut labels: Vec<Option<usize>> = vec![None; trace.len()];
+    let mut cluster_count = 0;
+
+    for start in 0..trace.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+
+        let mut queue = neighbours(start);
+        if queue.len() < min_points {
+            // Noise for now, a later cluster may still claim it as a border point
+            continue;
+        }
+
+        let cluster = cluster_count;
+        cluster_count += 1;
+        labels[start] = Some(cluster);
+
+        while let Some(i) = queue.pop() {
+            if labels[i].is_none() {
+                labels[i] = Some(cluster);
+            }
+            if !visited[i] {
+                visited[i] = true;
+                let expansion = neighbours(i);
+                if expansion.len() >= min_points {
+                    queue.extend(expansion);
+                }
+            }
+        }
+    }
+
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); cluster_count];
+    let mut noise = Vec::new();
+    for (i, label) in labels.iter().enumerate() {
+        match label {
+            Some(cluster) => members[*cluster].push(i),
+            None => noise.push(trace[i].id),
+        }
+    }
+
+    let clusters = members
+        .into_iter()
+        .map(|indices| {
+            let positions: Vec<GeoPoint> = indices.iter().map(|&i| geo[i]).collect();
+            Cluster {
+                members: indices.iter().map(|&i| trace[i].id).collect(),
+                centroid: centroid(&positions),
+            }
+        })
+        .collect();
+
+    Clustering { clusters, noise }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::trace;
+
+    /// Tests two stops separated by a long flight, with one stray point
+    ///
+    /// Ids 1-4 hover around Rovaniemi, id 5 is halfway to Paris, ids 6-8
+    /// hover around Paris. With eps = 5 km and 3 points per cluster, id 5 is
+    /// noise.
+    #[test]
+    fn test_dbscan() -> eyre::Result<()> {
+        let trace = trace(&[
+            (66.50, 25.72),
+            (66.51, 25.73),
+            (66.50, 25.74),
+            (66.51, 25.72),
+            (57.0, 14.0),
+            (48.85, 2.35),
+            (48.86, 2.36),
+            (48.85, 2.34),
+        ])?;
+
+        let clustering = dbscan(&trace, Distance::from_km(5.0), 3);
+
+        assert_eq!(clustering.clusters.len(), 2);
+        assert_eq!(clustering.clusters[0].members, vec![1, 2, 3, 4]);
+        assert_eq!(clustering.clusters[0].id_span(), 3);
+        assert_eq!(clustering.clusters[1].members, vec![6, 7, 8]);
+        assert_eq!(clustering.noise, vec![5]);
+
+        let paris = clustering.clusters[1].centroid;
+        assert!((paris.lat() - 48.8533).abs() < 1e-3 && (paris.lon() - 2.35).abs() < 1e-3);
+
+        // Spans wider than i32 do not overflow
+        let wide = Cluster {
+            members: vec![i32::MIN, i32::MAX],
+            centroid: paris,
+        };
+        assert_eq!(wide.id_span(), i64::from(u32::MAX));
+
+        Ok(())
+    }
+
+    /// Tests that a point reachable only from a core point is a border member,
+    /// and that clusters work across the antimeridian
+    #[test]
+    fn test_dbscan_border_and_antimeridian() -> eyre::Result<()> {
+        // 0.02° of longitude at the equator is ~2.2 km: with eps = 2.5 km,
+        // ids 1-3 reach each other, id 4 only reaches id 3
+        let trace = trace(&[(0.0, 179.99), (0.0, -179.99), (0.0, 179.97), (0.0, 179.95)])?;
+
+        let clustering = dbscan(&trace, Distance::from_km(2.5), 3);
+
+        assert_eq!(clustering.clusters.len(), 1);
+        assert_eq!(clustering.clusters[0].members, vec![1, 2, 3, 4]);
+        assert!(clustering.noise.is_empty());
+        assert!(clustering.clusters[0].centroid.lon().abs() > 179.9);
+
+        Ok(())
+    }
+}
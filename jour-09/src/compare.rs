@@ -0,0 +1,197 @@
+use eyre::Result;
+
+use crate::geo::{Distance, GeoPoint, TracePoint};
+use crate::spatial::SpatialIndex;
+
+/// A planned point matched with an actual point, and their great-circle distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointPair {
+    pub planned_id: i32,
+    pub actual_id: i32,
+    pub distance: Distance,
+}
+
+/// How far an actual trace deviated from the planned route
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Discrete Fréchet distance, with the pair that sets it
+    pub frechet: PointPair,
+    /// Hausdorff distance, with the pair that sets it
+    pub hausdorff: PointPair,
+    /// Pairs of the optimal Fréchet coupling, farthest first
+    pub divergences: Vec<PointPair>,
+}
+
+/// Compares two sorted traces, keeping the `top` most divergent pairs
+///
+/// Hausdorff ignores the order of the points: it is the largest distance from
+/// a point of one trace to the closest point of the other. Fréchet walks both
+/// traces forward together, so a route flown backwards scores badly even if
+/// it covers the same places.
+///
+/// # Errors
+/// Returns an error if either trace is empty
+pub fn compare(planned: &[TracePoint], actual: &[TracePoint], top: usize) -> Result<Comparison> {
+    if planned.is_empty() || actual.is_empty() {
+        eyre::bail!("Cannot compare an empty trace");
+    }
+
+    let coupling = frechet_coupling(planned, actual);
+    let frechet = coupling
+        .iter()
+        .copied()
+        .max_by(|a, b| a.distance.km().total_cmp(&b.distance.km()))
+        .expect("the coupling holds at least the two end pairs");
+
+    let mut divergences = coupling;
+    divergences.sort_by(|a, b| b.distance.km().total_cmp(&a.distance.km()));
+    divergences.truncate(top);
+
+    Ok(Comparison {
+        frechet,
+        hausdorff: hausdorff(planned, actual),
+        divergences,
+    })
+}
+
+/// Farthest nearest-neighbour in both directions, using the spatial index
+fn hausdorff(planned: &[TracePoint], actual: &[TracePoint]) -> PointPair {
+    let planned_index = SpatialIndex::build(planned);
+    let actual_index = SpatialIndex::build(actual);
+
+    let from_planned = planned.iter().filter_map(|point| {
+        let nearest = actual_index.nearest(point.geo(), 1).pop()?;
+        Some(PointPair {
+            planned_id: point.id,
+            actual_id: nearest.id,
+            distance: nearest.distance,
+        })
+    });
+    let from_actual = actual.iter().filter_map(|point| {
+        let nearest = planned_index.nearest(point.geo(), 1).pop()?;
+        Some(PointPair {
+            planned_id: nearest.id,
+            actual_id: point.id,
+            distance: nearest.distance,
+        })
+    });
+
+    from_planned
+        .chain(from_actual)
+        .max_by(|a, b| a.distance.km().total_cmp(&b.distance.km()))
+        .expect("both traces are non-empty")
+}
+
+/// Optimal discrete Fréchet coupling of two non-empty traces, in walk order
+///
+/// Classic dynamic programming (Eiter & Mannila): cell (i, j) holds the best
+/// achievable maximum distance for walks ending at planned[i] and actual[j].
+/// The table takes O(n·m) time and memory, which is fine for traces of a few
+/// thousand points.
+fn frechet_coupling(planned: &[TracePoint], actual: &[TracePoint]) -> Vec<PointPair> {
+    let a: Vec<GeoPoint> = planned.iter().map(|p| p.geo()).collect();
+    let b: Vec<GeoPoint> = actual.iter().map(|p| p.geo()).collect();
+    let (n, m) = (a.len(), b.len());
+    let distance = |i: usize, j: usize| a[i].great_circle_distance(b[j]).km();
+
+    let mut table: Vec<f64> = vec![0.0; n * m];
+    for i in 0..n {
+        for j in 0..m {
+            let best_before = match (i, j) {
+                (0, 0) => 0.0,
+                (0, _) => table[j - 1],
+                (_, 0) => table[(i - 1) * m],
+                _ => table[(i - 1) * m + j - 1]
+                    .min(table[(i - 1) * m + j])
+                    .min(table[i * m + j - 1]),
+            };
+            table[i * m + j] = distance(i, j).max(best_before);
+        }
+    }
+
+    // Walk back from the end, preferring diagonal moves on ties
+    let pair = |i: usize, j: usize| PointPair {
+        planned_id: planned[i].id,
+        actual_id: actual[j].id,
+        distance: Distance::from_km(distance(i, j)),
+    };
+    let (mut i, mut j) = (n - 1, m - 1);
+    let mut coupling = vec![pair(i, j)];
+    while i > 0 || j > 0 {
+        (i, j) = match (i, j) {
+            (0, _) => (0, j - 1),
+            (_, 0) => (i - 1, 0),
+            _ => {
+                let diagonal = table[(i - 1) * m + j - 1];
+                if diagonal <= table[(i - 1) * m + j] && diagonal <= table[i * m + j - 1] {
+                    (i - 1, j - 1)
+                } else if table[(i - 1) * m + j] <= table[i * m + j - 1] {
+                    (i - 1, j)
+                } else {
+                    (i, j - 1)
+                }
+            }
+        };
+        coupling.push(pair(i, j));
+    }
+
+    coupling.reverse();
+    coupling
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::equator;
+
+    /// Tests that a trace compared with itself does not diverge
+    #[test]
+    fn test_compare_identical() -> Result<()> {
+        let trace = equator(&[0.0, 1.0, 2.0, 3.0])?;
+        let comparison = compare(&trace, &trace, 3)?;
+
+        assert!(comparison.frechet.distance.km() < 1e-6);
+        assert!(comparison.hausdorff.distance.km() < 1e-6);
+        assert_eq!(comparison.divergences.len(), 3);
+
+        Ok(())
+    }
+
+    /// Tests a detour: the actual trace stops 2° further east than planned
+    ///
+    /// One degree of longitude at the equator is about 111.19 km.
+    #[test]
+    fn test_compare_detour() -> Result<()> {
+        let planned = equator(&[0.0, 1.0, 2.0])?;
+        let actual = equator(&[0.0, 1.0, 2.0, 4.0])?;
+        let comparison = compare(&planned, &actual, 1)?;
+
+        assert!((comparison.hausdorff.distance.km() - 2.0 * 111.19).abs() < 0.1);
+        assert_eq!(
+            (
+                comparison.hausdorff.planned_id,
+                comparison.hausdorff.actual_id
+            ),
+            (3, 4)
+        );
+        assert_eq!(comparison.frechet, comparison.divergences[0]);
+        assert_eq!(comparison.frechet.actual_id, 4);
+
+        Ok(())
+    }
+
+    /// Tests that Fréchet, unlike Hausdorff, sees a route flown backwards
+    #[test]
+    fn test_compare_reversed() -> Result<()> {
+        let planned = equator(&[0.0, 1.0, 2.0])?;
+        let actual = equator(&[2.0, 1.0, 0.0])?;
+        let comparison = compare(&planned, &actual, 5)?;
+
+        assert!(comparison.hausdorff.distance.km() < 1e-6);
+        assert!((comparison.frechet.distance.km() - 2.0 * 111.19).abs() < 0.1);
+
+        assert!(compare(&planned, &[], 5).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use eyre::Result;
+use std::io::Write;
+
+use crate::geo::{Distance, MercatorPoint, TracePoint};
+
+/// A point of a densified trace; inserted points get fractional ids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DensePoint {
+    pub id: f64,
+    pub position: MercatorPoint,
+}
+
+/// Inserts great-circle points into every segment of the sorted trace so
+/// that no two consecutive points are more than `step` apart
+///
+/// A segment of length L is cut into ceil(L / step) equal parts. The
+/// original points are kept unchanged, inserted points get ids spread
+/// evenly between the ids of the segment ends.
+///
+/// # Errors
+/// Returns an error if `step` is not strictly positive
+pub fn densify(trace: &[TracePoint], step: Distance) -> Result<Vec<DensePoint>> {
+    if step.km() <= 0.0 {
+        eyre::bail!("The densify step must be positive, got {} km", step.km());
+    }
+
+    let mut dense = Vec::with_capacity(trace.len());
+    for pair in trace.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let (start, end) = (a.geo(), b.geo());
+        let parts = (start.great_circle_distance(end).km() / step.km())
+            .ceil()
+            .max(1.0) as usize;
+
+        dense.push(DensePoint {
+            id: f64::from(a.id),
+            position: a.position,
+        });
+        for k in 1..parts {
+            let fraction = k as f64 / parts as f64;
+            dense.push(DensePoint {
+                id: f64::from(a.id) + fraction * (f64::from(b.id) - f64::from(a.id)),
+                position: start.interpolate(end, fraction).to_mercator(),
+            });
+        }
+    }
+    if let Some(last) = trace.last() {
+        dense.push(DensePoint {
+            id: f64::from(last.id),
+            position: last.position,
+        });
+    }
+
+    Ok(dense)
+}
+
+/// Writes the points as `id,x,y` lines, with ids renumbered 1, 2, 3...
+/// when `renumber` is set
+pub fn write_trace<W: Write>(mut writer: W, points: &[DensePoint], renumber: bool) -> Result<()> {
+    for (index, point) in points.iter().enumerate() {
+        if renumber {
+            write!(writer, "{}", index + 1)?;
+        } else {
+            write!(writer, "{}", point.id)?;
+        }
+        writeln!(writer, ",{},{}", point.position.x, point.position.y)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::equator;
+
+    /// Tests the number of inserted points, their ids and spacing
+    #[test]
+    fn test_densify() -> Result<()> {
+        // Two points 3° of longitude apart on the equator (~333.6 km), ids 1 and 2
+        let trace = equator(&[0.0, 3.0])?;
+        let dense = densify(&trace, Distance::from_km(100.0))?;
+
+        // 333.6 km in steps of at most 100 km: 4 parts, 3 inserted points
+        assert_eq!(
+            dense.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![1.0, 1.25, 1.5, 1.75, 2.0]
+        );
+        assert_eq!(dense[0].position, trace[0].position);
+        assert_eq!(dense[4].position, trace[1].position);
+        assert!((dense[2].position.to_wgs84().lon() - 1.5).abs() < 1e-9);
+
+        // A step longer than the segment keeps the trace as is
+        assert_eq!(densify(&trace, Distance::from_km(1000.0))?.len(), 2);
+        assert!(densify(&trace, Distance::default()).is_err());
+
+        // Ids far apart do not overflow when interpolated
+        let wide = [
+            TracePoint {
+                id: i32::MIN,
+                ..trace[0]
+            },
+            TracePoint {
+                id: i32::MAX,
+                ..trace[1]
+            },
+        ];
+        assert_eq!(densify(&wide, Distance::from_km(100.0))?[2].id, -0.5);
+
+        Ok(())
+    }
+
+    /// Tests the id,x,y output with fractional and renumbered ids
+    #[test]
+    fn test_write_trace() -> Result<()> {
+        let points = [
+            DensePoint {
+                id: 1.0,
+                position: MercatorPoint::new(0.0, 0.0),
+            },
+            DensePoint {
+                id: 1.5,
+                position: MercatorPoint::new(10.5, -2.0),
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_trace(&mut out, &points, false)?;
+        assert_eq!(String::from_utf8(out)?, "1,0,0\n1.5,10.5,-2\n");
+
+        let mut out = Vec::new();
+        write_trace(&mut out, &points, true)?;
+        assert_eq!(String::from_utf8(out)?, "1,0,0\n2,10.5,-2\n");
+
+        Ok(())
+    }
+}
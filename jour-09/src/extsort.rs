@@ -0,0 +1,309 @@
+use eyre::{Context, Result};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use tempfile::{NamedTempFile, TempPath};
+
+use crate::geo::TracePoint;
+use crate::reader::Record;
+
+/// Bytes of one spilled record: id (i32), line (u64), x and y (f64), little endian
+const RECORD_BYTES: usize = 4 + 8 + 8 + 8;
+
+/// Default number of records sorted in memory before spilling a run
+pub const DEFAULT_RUN_SIZE: usize = 1_000_000;
+
+/// Maximum number of runs merged at once
+///
+/// Every run being merged holds an open file: beyond this many runs, they are
+/// first merged by groups into longer runs, pass after pass.
+pub const MAX_FAN_IN: usize = 64;
+
+/// Writes records, already in (id, line) order, to a new temporary file
+///
+/// The file is closed once written and deleted when the path is dropped.
+fn write_run(records: impl Iterator<Item = Result<(usize, TracePoint)>>) -> Result<TempPath> {
+    let mut file = NamedTempFile::new().wrap_err("Failed to create a temporary run file")?;
+    let mut writer = BufWriter::new(file.as_file_mut());
+    for record in records {
+        let (line, point) = record?;
+        writer.write_all(&point.id.to_le_bytes())?;
+        writer.write_all(&(line as u64).to_le_bytes())?;
+        writer.write_all(&point.position.x.to_le_bytes())?;
+        writer.write_all(&point.position.y.to_le_bytes())?;
+    }
+    writer
+        .flush()
+        .wrap_err("Failed to write a temporary run file")?;
+    drop(writer);
+
+    Ok(file.into_temp_path())
+}
+
+/// Sorts the records by (id, line) and spills them to a new run file
+fn spill(records: &mut [(usize, TracePoint)]) -> Result<TempPath> {
+    records.sort_unstable_by_key(|&(line, point)| (point.id, line));
+    write_run(records.iter().map(|&record| Ok(record)))
+}
+
+/// A sorted run being read back, its file deleted when dropped
+struct Run {
+    reader: BufReader<File>,
+    _path: TempPath,
+}
+
+impl Run {
+    fn open(path: TempPath) -> Result<Self> {
+        let file = File::open(&path).wrap_err("Failed to open a temporary run file")?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            _path: path,
+        })
+    }
+
+    /// Reads the next record of the run, None at the end
+    fn next_record(&mut self) -> Result<Option<(usize, TracePoint)>> {
+        let mut buffer = [0u8; RECORD_BYTES];
+        match self.reader.read_exact(&mut buffer) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err).wrap_err("Failed to read a temporary run file"),
+        }
+
+        let field = |range: std::ops::Range<usize>| -> [u8; 8] {
+            buffer[range].try_into().expect("8-byte field")
+        };
+        let id = i32::from_le_bytes(buffer[0..4].try_into().expect("4-byte field"));
+        let line = u64::from_le_bytes(field(4..12)) as usize;
+        let x = f64::from_le_bytes(field(12..20));
+        let y = f64::from_le_bytes(field(20..28));
+
+        Ok(Some((line, TracePoint::new(id, x, y))))
+    }
+}
+
+/// Records merged from the sorted runs, in (id, line) order
+pub struct MergedRuns {
+    runs: Vec<Run>,
+    /// Head of every non-exhausted run, keyed by (id, line, run index)
+    heap: BinaryHeap<Reverse<(i32, usize, usize)>>,
+    /// Position of the head record of every run (the heap only holds keys)
+    heads: Vec<Option<TracePoint>>,
+}
+
+impl MergedRuns {
+    fn new(paths: Vec<TempPath>) -> Result<Self> {
+        let mut runs = paths
+            .into_iter()
+            .map(Run::open)
+            .collect::<Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+        let mut heads = Vec::with_capacity(runs.len());
+
+        for (index, run) in runs.iter_mut().enumerate() {
+            let head = run.next_record()?;
+            if let Some((line, point)) = head {
+                heap.push(Reverse((point.id, line, index)));
+            }
+            heads.push(head.map(|(_, point)| point));
+        }
+
+        Ok(Self { runs, heap, heads })
+    }
+}
+
+impl Iterator for MergedRuns {
+    type Item = Result<(usize, TracePoint)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, line, index)) = self.heap.pop()?;
+        let point = self.heads[index].take()?;
+
+        // Refill the heap from the run the record came from
+        match self.runs[index].next_record() {
+            Ok(Some((next_line, next))) => {
+                self.heap.push(Reverse((next.id, next_line, index)));
+                self.heads[index] = Some(next);
+            }
+            Ok(None) => {}
+            Err(err) => return Some(Err(err)),
+        }
+
+        Some(Ok((line, point)))
+    }
+}
+
+/// External merge sort of a record stream by (id, line)
+///
+/// At most `run_size` records are held in memory: every full batch is sorted
+/// and spilled to a temporary file, then the runs are merged lazily with a
+/// heap, keeping one record per run in memory. Runs are merged at most
+/// `MAX_FAN_IN` at a time, so the number of open files stays bounded however
+/// long the input is. Temporary files are removed when the returned iterator
+/// is dropped.
+///
+/// # Errors
+/// Returns the first reading error, or an error if the records belong to
+/// more than one sleigh (split fleet logs first)
+pub fn external_sort(
+    records: impl Iterator<Item = Result<Record>>,
+    run_size: usize,
+) -> Result<MergedRuns> {
+    sort_with_fan_in(records, run_size, MAX_FAN_IN)
+}
+
+/// `external_sort` merging at most `fan_in` runs at once
+///
+/// While there are more than `fan_in` runs, each pass merges them by groups of
+/// `fan_in` into longer runs, rewriting every record once per pass. The last
+/// `fan_in` runs or less are merged lazily.
+fn sort_with_fan_in(
+    records: impl Iterator<Item = Result<Record>>,
+    run_size: usize,
+    fan_in: usize,
+) -> Result<MergedRuns> {
+    let run_size = run_size.max(1);
+    // Merging a single run at a time would never end
+    let fan_in = fan_in.max(2);
+    let mut runs = Vec::new();
+    let mut batch: Vec<(usize, TracePoint)> = Vec::with_capacity(run_size.min(DEFAULT_RUN_SIZE));
+    let mut sleigh: Option<String> = None;
+
+    for record in records {
+        let record = record?;
+        match (&sleigh, record.sleigh) {
+            (Some(first), Some(other)) if *first != other => eyre::bail!(
+                "line {}: sleigh '{}' after '{}', external sorting handles a single trace",
+                record.line,
+                other,
+                first
+            ),
+            (None, Some(first)) => sleigh = Some(first),
+            _ => {}
+        }
+
+        batch.push((record.line, record.point));
+        if batch.len() == run_size {
+            runs.push(spill(&mut batch)?);
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        runs.push(spill(&mut batch)?);
+    }
+
+    while runs.len() > fan_in {
+        let mut merged = Vec::with_capacity(runs.len().div_ceil(fan_in));
+        let mut pending = runs.into_iter();
+        loop {
+            let group: Vec<TempPath> = pending.by_ref().take(fan_in).collect();
+            if group.is_empty() {
+                break;
+            }
+            // The group's files are deleted as soon as the longer run is written
+            merged.push(write_run(MergedRuns::new(group)?)?);
+        }
+        runs = merged;
+    }
+
+    MergedRuns::new(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(line: usize, id: i32) -> Result<Record> {
+        Ok(Record {
+            line,
+            sleigh: None,
+            point: TracePoint::new(id, f64::from(id) * 1.5, -f64::from(line as u32)),
+        })
+    }
+
+    /// Tests that the merged runs come out in (id, line) order, exactly
+    ///
+    /// Run size 2 forces 4 runs; id 3 appears twice, on lines 2 and 6.
+    #[test]
+    fn test_external_sort_order() -> Result<()> {
+        let ids = [5, 3, 8, 1, 7, 3, 2];
+        let records = ids.iter().zip(1..).map(|(&id, line)| record(line, id));
+
+        let merged = external_sort(records, 2)?;
+        assert_eq!(merged.runs.len(), 4);
+
+        let sorted = merged.collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|&(line, point)| (point.id, line))
+                .collect::<Vec<_>>(),
+            vec![(1, 4), (2, 7), (3, 2), (3, 6), (5, 1), (7, 5), (8, 3)]
+        );
+        // Coordinates survive the round trip through the run files bit for bit
+        assert_eq!(sorted[0].1, record(4, 1)?.point);
+
+        Ok(())
+    }
+
+    /// Tests that more runs than the fan-in are merged by passes, in order
+    ///
+    /// 10 runs of one record with a fan-in of 3: a first pass leaves 4 runs,
+    /// a second one 2, merged lazily.
+    #[test]
+    fn test_external_sort_fan_in() -> Result<()> {
+        let ids = [9, 4, 6, 4, 1, 8, 0, 7, 2, 5];
+        let records = ids.iter().zip(1..).map(|(&id, line)| record(line, id));
+
+        let merged = sort_with_fan_in(records, 1, 3)?;
+        assert_eq!(merged.runs.len(), 2);
+
+        let sorted = merged.collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|&(line, point)| (point.id, line))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 7),
+                (1, 5),
+                (2, 9),
+                (4, 2),
+                (4, 4),
+                (5, 10),
+                (6, 3),
+                (7, 8),
+                (8, 6),
+                (9, 1)
+            ]
+        );
+        assert_eq!(sorted[4].1, record(4, 4)?.point);
+
+        // A fan-in below 2 is raised to 2 instead of looping forever
+        let records = ids.iter().zip(1..).map(|(&id, line)| record(line, id));
+        assert_eq!(sort_with_fan_in(records, 1, 1)?.count(), ids.len());
+
+        Ok(())
+    }
+
+    /// Tests empty input and the single-sleigh check
+    #[test]
+    fn test_external_sort_edge_cases() -> Result<()> {
+        assert_eq!(external_sort(std::iter::empty(), 10)?.count(), 0);
+
+        let fleet = ["comet", "dasher"].iter().zip(1..).map(|(&name, line)| {
+            Ok(Record {
+                sleigh: Some(name.to_string()),
+                ..record(line, line as i32)?
+            })
+        });
+        let err = external_sort(fleet, 10).err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("line 2: sleigh 'dasher' after 'comet'")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use eyre::{Context, Result};
+use std::collections::BTreeMap;
+
+use crate::geo::{Distance, TracePoint};
+use crate::reader::Record;
+use crate::validation::{self, Trace, ValidationPolicy, ValidationReport};
+
+/// The sorted trace of one sleigh of a fleet log
+#[derive(Debug, Clone)]
+pub struct SleighTrace {
+    pub sleigh: String,
+    pub trace: Trace,
+    pub report: ValidationReport,
+}
+
+/// Distances covered by one sleigh
+#[derive(Debug, Clone, PartialEq)]
+pub struct SleighSummary {
+    pub sleigh: String,
+    pub start_to_end: Distance,
+    pub path_length: Distance,
+    pub points: usize,
+}
+
+/// Totals over every sleigh of the fleet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FleetTotal {
+    pub sleighs: usize,
+    pub start_to_end: Distance,
+    pub path_length: Distance,
+    pub points: usize,
+}
+
+/// Groups the records by sleigh, then sorts and validates every trace on its own
+///
+/// Ids only need to be unique within a sleigh. Output is sorted by sleigh id.
+pub fn split_by_sleigh(
+    records: Vec<Record>,
+    policy: &ValidationPolicy,
+) -> Result<Vec<SleighTrace>> {
+    let mut groups: BTreeMap<String, Vec<(usize, TracePoint)>> = BTreeMap::new();
+
+    for record in records {
+        let sleigh = record.sleigh.ok_or_else(|| {
+            eyre::eyre!(
+                "line {}: no sleigh id (use --fleet or a 'sleigh_id' header column)",
+                record.line
+            )
+        })?;
+        groups
+            .entry(sleigh)
+            .or_default()
+            .push((record.line, record.point));
+    }
+
+    groups
+        .into_iter()
+        .map(|(sleigh, records)| {
+            let (trace, report) = validation::validate(records, policy)
+                .wrap_err_with(|| format!("sleigh '{}'", sleigh))?;
+            Ok(SleighTrace {
+                sleigh,
+                trace,
+                report,
+            })
+        })
+        .collect()
+}
+
+/// Sum of the great-circle distances between consecutive points
+pub fn path_length(trace: &[TracePoint]) -> Distance {
+    trace
+        .windows(2)
+        .map(|pair| pair[0].geo().great_circle_distance(pair[1].geo()))
+        .sum()
+}
+
+impl SleighTrace {
+    /// Start-to-end distance, path length and point count of the trace
+    pub fn summary(&self) -> SleighSummary {
+        let start_to_end = match (self.trace.first(), self.trace.last()) {
+            (Some(first), Some(last)) => first.geo().great_circle_distance(last.geo()),
+            _ => Distance::default(),
+        };
+
+        SleighSummary {
+            sleigh: self.sleigh.clone(),
+            start_to_end,
+            path_length: path_length(&self.trace),
+            points: self.trace.len(),
+        }
+    }
+}
+
+/// Adds up the per-sleigh summaries
+pub fn fleet_total(summaries: &[SleighSummary]) -> FleetTotal {
+    summaries
+        .iter()
+        .fold(FleetTotal::default(), |mut total, summary| {
+            total.sleighs += 1;
+            total.start_to_end += summary.start_to_end;
+            total.path_length += summary.path_length;
+            total.points += summary.points;
+            total
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(line: usize, sleigh: &str, id: i32, x: f64) -> Record {
+        Record {
+            line,
+            sleigh: Some(sleigh.to_string()),
+            point: TracePoint::new(id, x, 0.0),
+        }
+    }
+
+    /// Tests grouping, per-sleigh sorting and the fleet totals
+    ///
+    /// Both sleighs use id 1: ids only have to be unique within a sleigh.
+    /// Along the equator, 111_319.49 m of Mercator x is one degree.
+    #[test]
+    fn test_split_by_sleigh() -> Result<()> {
+        let degree = 111_319.490_793;
+        let records = vec![
+            record(1, "rudolph", 2, 2.0 * degree),
+            record(2, "comet", 1, 0.0),
+            record(3, "rudolph", 1, 0.0),
+            record(4, "rudolph", 3, 1.0 * degree),
+            record(5, "comet", 2, 5.0 * degree),
+        ];
+
+        let traces = split_by_sleigh(records, &ValidationPolicy::default())?;
+        assert_eq!(
+            traces.iter().map(|t| t.sleigh.as_str()).collect::<Vec<_>>(),
+            vec!["comet", "rudolph"]
+        );
+        assert_eq!(
+            traces[1].trace.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        // rudolph goes 0° → 2° → 1°: 1° from start to end, 3° of path
+        let summaries: Vec<SleighSummary> = traces.iter().map(SleighTrace::summary).collect();
+        let one_degree_km = 111.19;
+        assert!((summaries[1].start_to_end.km() - one_degree_km).abs() < 0.1);
+        assert!((summaries[1].path_length.km() - 3.0 * one_degree_km).abs() < 0.1);
+
+        let total = fleet_total(&summaries);
+        assert_eq!((total.sleighs, total.points), (2, 5));
+        assert!((total.path_length.km() - 8.0 * one_degree_km).abs() < 0.1);
+
+        Ok(())
+    }
+
+    /// Tests that validation errors name the sleigh
+    #[test]
+    fn test_split_by_sleigh_errors() {
+        let records = vec![record(1, "comet", 1, 0.0), record(2, "comet", 1, 1.0)];
+        let err = split_by_sleigh(records, &ValidationPolicy::default()).unwrap_err();
+        assert!(format!("{:#}", err).contains("sleigh 'comet'"));
+
+        let unnamed = vec![Record {
+            line: 4,
+            sleigh: None,
+            point: TracePoint::new(1, 0.0, 0.0),
+        }];
+        let err = split_by_sleigh(unnamed, &ValidationPolicy::default()).unwrap_err();
+        assert!(err.to_string().contains("line 4: no sleigh id"));
+    }
+}
@@ -0,0 +1,668 @@
+use clap::ValueEnum;
+use eyre::Result;
+use std::f64::consts::PI;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul};
+use std::str::FromStr;
+
+/// WGS84 Earth equatorial radius in meters, as used by Web Mercator
+const EARTH_RADIUS_M: f64 = 6378137.0;
+
+/// Half the side of the Web Mercator square, in meters (π · R): the largest
+/// |x| (longitude 180°) and |y| (latitude about 85.05°) of valid points
+pub const MERCATOR_LIMIT_M: f64 = PI * EARTH_RADIUS_M;
+
+/// Latitude of the top and bottom edges of the Web Mercator square, in degrees
+pub const MERCATOR_MAX_LAT: f64 = 85.051_128_779_806_59;
+
+/// Earth's mean radius in kilometers, used for great-circle distances
+pub const EARTH_MEAN_RADIUS_KM: f64 = 6371.0;
+
+/// Meters in a statute mile
+const METERS_PER_MILE: f64 = 1609.344;
+
+/// Meters in a nautical mile
+const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+
+/// What to do with coordinates outside the Web Mercator square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DomainPolicy {
+    /// Fail, naming the coordinate and the limit
+    #[default]
+    Reject,
+    /// Bring x and y back to the nearest edge of the square; NaN and
+    /// infinities are still rejected
+    Clamp,
+}
+
+/// A point in the Web Mercator projection (EPSG:3857), in meters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MercatorPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A WGS84 position in degrees
+///
+/// Fields are private so that every value went through the latitude check
+/// of `GeoPoint::new`. The constructor takes (lat, lon), like the accessors,
+/// to avoid silent swaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    lat: f64,
+    lon: f64,
+}
+
+/// Units distances can be reported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Unit {
+    /// Kilometers
+    #[default]
+    Km,
+    /// Statute miles
+    Mi,
+    /// Nautical miles
+    Nmi,
+    /// Meters
+    M,
+}
+
+impl Unit {
+    /// Short name printed after values
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Km => "km",
+            Unit::Mi => "mi",
+            Unit::Nmi => "nmi",
+            Unit::M => "m",
+        }
+    }
+}
+
+/// A length, stored in meters, with conversions to the usual units
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Distance {
+    meters: f64,
+}
+
+/// A trace fix: its sequence id and its projected position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracePoint {
+    pub id: i32,
+    pub position: MercatorPoint,
+}
+
+impl MercatorPoint {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Checks raw coordinates against the Web Mercator square [-π·R, π·R]²
+    ///
+    /// # Errors
+    /// Returns an error if a coordinate is NaN or infinite, or, with
+    /// `DomainPolicy::Reject`, outside the square
+    pub fn validated(x: f64, y: f64, policy: DomainPolicy) -> Result<Self> {
+        for (name, value) in [("x", x), ("y", y)] {
+            if !value.is_finite() {
+                eyre::bail!("{} = {} is not a finite number", name, value);
+            }
+        }
+
+        match policy {
+            DomainPolicy::Reject => {
+                if x.abs() > MERCATOR_LIMIT_M {
+                    eyre::bail!(
+                        "x = {} m is beyond the Web Mercator limit of ±{:.2} m (longitude ±180°)",
+                        x,
+                        MERCATOR_LIMIT_M
+                    );
+                }
+                if y.abs() > MERCATOR_LIMIT_M {
+                    eyre::bail!(
+                        "y = {} m is beyond the Web Mercator limit of ±{:.2} m (latitude ±{:.4}°)",
+                        y,
+                        MERCATOR_LIMIT_M,
+                        MERCATOR_MAX_LAT
+                    );
+                }
+                Ok(Self { x, y })
+            }
+            DomainPolicy::Clamp => Ok(Self {
+                x: x.clamp(-MERCATOR_LIMIT_M, MERCATOR_LIMIT_M),
+                y: y.clamp(-MERCATOR_LIMIT_M, MERCATOR_LIMIT_M),
+            }),
+        }
+    }
+
+    /// Converts Web Mercator projection (x,y in meters) to WGS84 lat/lon (degrees)
+    /// Uses inverse Mercator projection formulas
+    /// Points from `validated` map to finite positions; NaN propagates
+    pub fn to_wgs84(self) -> GeoPoint {
+        // Convert x (meters) to longitude (degrees)
+        // Formula: lon = (x / r) * (180 / π)
+        let lon = (self.x / EARTH_RADIUS_M) * 180.0 / PI;
+
+        // Convert y (meters) to latitude (degrees) using inverse Mercator projection
+        // Formula: lat = (2 * arctan(e^(y/r)) - π/2) * (180 / π)
+        // The result always lies strictly between -90° and 90°
+        let lat = (2.0 * (self.y / EARTH_RADIUS_M).exp().atan() - PI / 2.0) * 180.0 / PI;
+
+        GeoPoint { lat, lon }
+    }
+}
+
+impl GeoPoint {
+    /// Creates a position from latitude and longitude in degrees
+    ///
+    /// # Errors
+    /// Returns an error if a coordinate is not finite or if the latitude is
+    /// outside [-90°, 90°]
+    pub fn new(lat: f64, lon: f64) -> Result<Self> {
+        if !lat.is_finite() || !lon.is_finite() {
+            eyre::bail!(
+                "Coordinates must be finite numbers (lat {}, lon {})",
+                lat,
+                lon
+            );
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            eyre::bail!("Latitude {} is outside [-90°, 90°]", lat);
+        }
+        Ok(Self { lat, lon })
+    }
+
+    /// Latitude in degrees
+    pub fn lat(self) -> f64 {
+        self.lat
+    }
+
+    /// Longitude in degrees
+    pub fn lon(self) -> f64 {
+        self.lon
+    }
+
+    /// Converts WGS84 lat/lon (degrees) to Web Mercator (x,y in meters)
+    /// Inverse of `MercatorPoint::to_wgs84`
+    /// The poles are at infinity in Mercator, so latitudes beyond ±85.05° are
+    /// clamped to the edge of the square to keep y finite
+    pub fn to_mercator(self) -> MercatorPoint {
+        let lat = self.lat.clamp(-MERCATOR_MAX_LAT, MERCATOR_MAX_LAT);
+        let x = self.lon.to_radians() * EARTH_RADIUS_M;
+        let y = (PI / 4.0 + lat.to_radians() / 2.0).tan().ln() * EARTH_RADIUS_M;
+        MercatorPoint { x, y }
+    }
+
+    /// Calculates great-circle distance to another point
+    ///
+    /// The haversine formula loses precision near antipodal points (1 - a
+    /// cancels out) and can even return NaN when rounding pushes a above 1,
+    /// so the central angle is computed with the atan2 form of the Vincenty
+    /// formula on a sphere, accurate from identical to antipodal points.
+    pub fn great_circle_distance(self, other: GeoPoint) -> Distance {
+        // Convert all coordinates from degrees to radians
+        let lat1_rad = self.lat.to_radians();
+        let lat2_rad = other.lat.to_radians();
+        let dlon = (other.lon - self.lon).to_radians();
+
+        // c = atan2(√((cos lat2 · sin Δlon)² + (cos lat1 · sin lat2 − sin lat1 · cos lat2 · cos Δlon)²),
+        //           sin lat1 · sin lat2 + cos lat1 · cos lat2 · cos Δlon)
+        let y = (lat2_rad.cos() * dlon.sin())
+            .hypot(lat1_rad.cos() * lat2_rad.sin() - lat1_rad.sin() * lat2_rad.cos() * dlon.cos());
+        let x = lat1_rad.sin() * lat2_rad.sin() + lat1_rad.cos() * lat2_rad.cos() * dlon.cos();
+        let c = y.atan2(x);
+
+        // Multiply by Earth's mean radius (6371 km) to get distance in kilometers
+        Distance::from_km(EARTH_MEAN_RADIUS_KM * c)
+    }
+
+    /// Initial great-circle bearing towards another point, in degrees
+    /// clockwise from north in [0°, 360°)
+    ///
+    /// The bearing of a point towards itself is 0°.
+    pub fn initial_bearing(self, other: GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlon = (other.lon - self.lon).to_radians();
+
+        // θ = atan2(sin Δlon · cos lat2, cos lat1 · sin lat2 − sin lat1 · cos lat2 · cos Δlon)
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Point at `fraction` (0 = self, 1 = other) of the great-circle path to another point
+    ///
+    /// Spherical linear interpolation between the two unit vectors. When the
+    /// points are (nearly) identical or antipodal the great circle is not
+    /// defined numerically, and latitude and longitude are interpolated linearly.
+    pub fn interpolate(self, other: GeoPoint, fraction: f64) -> GeoPoint {
+        let (lat1, lon1) = (self.lat.to_radians(), self.lon.to_radians());
+        let (lat2, lon2) = (other.lat.to_radians(), other.lon.to_radians());
+        let delta = self.great_circle_distance(other).km() / EARTH_MEAN_RADIUS_KM;
+
+        if delta.sin() < 1e-12 {
+            return GeoPoint {
+                lat: self.lat + (other.lat - self.lat) * fraction,
+                lon: self.lon + (other.lon - self.lon) * fraction,
+            };
+        }
+
+        // Weights of the two end vectors: sin((1 − f)·δ) / sin δ and sin(f·δ) / sin δ
+        let a = ((1.0 - fraction) * delta).sin() / delta.sin();
+        let b = (fraction * delta).sin() / delta.sin();
+
+        let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+        let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+        let z = a * lat1.sin() + b * lat2.sin();
+
+        GeoPoint {
+            lat: z.atan2(x.hypot(y)).to_degrees(),
+            lon: y.atan2(x).to_degrees(),
+        }
+    }
+
+    /// Great-circle bearing on arrival at another point, in degrees
+    ///
+    /// The heading changes along a great circle, so this differs from the
+    /// initial bearing: it is the reverse of the initial bearing of the way back.
+    pub fn final_bearing(self, other: GeoPoint) -> f64 {
+        (other.initial_bearing(self) + 180.0).rem_euclid(360.0)
+    }
+}
+
+impl Distance {
+    pub fn from_meters(meters: f64) -> Self {
+        Self { meters }
+    }
+
+    pub fn from_km(km: f64) -> Self {
+        Self::from_meters(km * 1000.0)
+    }
+
+    pub fn meters(self) -> f64 {
+        self.meters
+    }
+
+    pub fn km(self) -> f64 {
+        self.meters / 1000.0
+    }
+
+    pub fn miles(self) -> f64 {
+        self.meters / METERS_PER_MILE
+    }
+
+    pub fn nautical_miles(self) -> f64 {
+        self.meters / METERS_PER_NAUTICAL_MILE
+    }
+
+    /// Value of the distance in the given unit
+    pub fn in_unit(self, unit: Unit) -> f64 {
+        match unit {
+            Unit::Km => self.km(),
+            Unit::Mi => self.miles(),
+            Unit::Nmi => self.nautical_miles(),
+            Unit::M => self.meters(),
+        }
+    }
+}
+
+/// Parses a number with an optional unit: "10km", "500 m", "3mi", "2nmi"
+///
+/// A bare number is in kilometers.
+impl FromStr for Distance {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+        let (value, unit) = (s[..split].trim(), &s[split..]);
+
+        let invalid = || {
+            format!(
+                "invalid distance '{}' (expected e.g. 10km, 500m, 3mi, 2nmi)",
+                s
+            )
+        };
+        let value: f64 = value.parse().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+
+        let meters_per_unit = match unit {
+            "" | "km" => 1000.0,
+            "m" => 1.0,
+            "mi" => METERS_PER_MILE,
+            "nmi" => METERS_PER_NAUTICAL_MILE,
+            _ => return Err(invalid()),
+        };
+        Ok(Distance::from_meters(value * meters_per_unit))
+    }
+}
+
+impl Add for Distance {
+    type Output = Distance;
+
+    fn add(self, other: Distance) -> Distance {
+        Distance::from_meters(self.meters + other.meters)
+    }
+}
+
+impl AddAssign for Distance {
+    fn add_assign(&mut self, other: Distance) {
+        self.meters += other.meters;
+    }
+}
+
+impl Mul<f64> for Distance {
+    type Output = Distance;
+
+    fn mul(self, factor: f64) -> Distance {
+        Distance::from_meters(self.meters * factor)
+    }
+}
+
+impl Sum for Distance {
+    fn sum<I: Iterator<Item = Distance>>(iter: I) -> Distance {
+        iter.fold(Distance::default(), Add::add)
+    }
+}
+
+impl TracePoint {
+    pub fn new(id: i32, x: f64, y: f64) -> Self {
+        Self {
+            id,
+            position: MercatorPoint::new(x, y),
+        }
+    }
+
+    /// Position of the fix in WGS84 degrees
+    pub fn geo(self) -> GeoPoint {
+        self.position.to_wgs84()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests Web Mercator to WGS84 coordinate conversion
+    ///
+    /// Verifies two scenarios:
+    /// 1. Typical case: arbitrary coordinates produce valid lat/lon ranges
+    /// 2. Origin case: (0,0) in Web Mercator maps to (0°, 0°) in WGS84
+    ///    (equator and prime meridian intersection)
+    #[test]
+    fn test_to_wgs84() {
+        // Test typical conversion with positive coordinates
+        let point = MercatorPoint::new(1000.0, 2000.0).to_wgs84();
+        assert!(point.lon() > 0.0); // Should be positive longitude
+        assert!(point.lat() > -90.0 && point.lat() < 90.0); // Valid latitude range
+
+        // Test origin point: (0, 0) should map to equator/prime meridian
+        let point = MercatorPoint::new(0.0, 0.0).to_wgs84();
+        assert!(point.lon().abs() < 1e-10); // Longitude ≈ 0° (prime meridian)
+        assert!(point.lat().abs() < 1e-10); // Latitude ≈ 0° (equator)
+    }
+
+    /// Tests extreme coordinate conversion at the edge of the projection
+    ///
+    /// When x equals Earth's radius times π (r × π), the resulting
+    /// longitude should be exactly 180° (the antimeridian, opposite of
+    /// the prime meridian). This tests the boundary behavior of the
+    /// Web Mercator to WGS84 conversion.
+    #[test]
+    fn test_to_wgs84_extreme() {
+        // x = r × π should map to longitude = 180° (antimeridian)
+        let point = MercatorPoint::new(6_378_137.0 * PI, 0.0).to_wgs84();
+
+        // Verify longitude is at the antimeridian with floating-point precision
+        assert!((point.lon() - 180.0).abs() < 1e-10);
+    }
+
+    /// Tests that to_mercator is the inverse of to_wgs84
+    #[test]
+    fn test_to_mercator_roundtrip() -> Result<()> {
+        let point = GeoPoint::new(48.8566, 2.3522)?.to_mercator().to_wgs84();
+        assert!((point.lon() - 2.3522).abs() < 1e-9 && (point.lat() - 48.8566).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    /// Tests great-circle distance calculation between geographic points
+    ///
+    /// Verifies two scenarios:
+    /// 1. Zero distance: same point should return distance ≈ 0
+    /// 2. Known distance: Paris to New York is approximately 5850 km
+    ///    (allows 50 km margin for Earth radius approximation)
+    #[test]
+    fn test_great_circle_distance() -> Result<()> {
+        let paris = GeoPoint::new(48.8566, 2.3522)?;
+        let new_york = GeoPoint::new(40.7128, -74.0060)?;
+
+        // Test zero distance (Paris to Paris)
+        assert!(paris.great_circle_distance(paris).km() < 1e-6); // Should be essentially zero
+
+        // Test known distance: Paris (48.8566°N, 2.3522°E) to New York (40.7128°N, 74.0060°W)
+        let distance = paris.great_circle_distance(new_york);
+        assert!((distance.km() - 5850.0).abs() < 50.0); // Should be ~5850 km ±50 km
+
+        Ok(())
+    }
+
+    /// Tests great-circle bearings
+    ///
+    /// Along a meridian the bearing is 0° (north) both ways round, along the
+    /// equator 90° (east). From Paris to New York the route leaves heading
+    /// north-west (~292°) and arrives heading south-west (~234°).
+    #[test]
+    fn test_bearings() -> Result<()> {
+        let origin = GeoPoint::new(0.0, 0.0)?;
+        assert!(origin.initial_bearing(GeoPoint::new(10.0, 0.0)?).abs() < 1e-9);
+        assert!((origin.initial_bearing(GeoPoint::new(0.0, 10.0)?) - 90.0).abs() < 1e-9);
+        assert!((origin.initial_bearing(GeoPoint::new(0.0, -10.0)?) - 270.0).abs() < 1e-9);
+
+        let paris = GeoPoint::new(48.8566, 2.3522)?;
+        let new_york = GeoPoint::new(40.7128, -74.0060)?;
+        assert!((paris.initial_bearing(new_york) - 291.8).abs() < 0.5);
+        assert!((paris.final_bearing(new_york) - 233.7).abs() < 0.5);
+
+        Ok(())
+    }
+
+    /// Tests great-circle interpolation
+    ///
+    /// Halfway along the equator is on the equator; halfway from the equator
+    /// to the pole along a meridian is at 45°; halfway between two points at
+    /// 60°N on opposite sides of the globe is the pole, not 60°N.
+    #[test]
+    fn test_interpolate() -> Result<()> {
+        let origin = GeoPoint::new(0.0, 0.0)?;
+
+        let mid = origin.interpolate(GeoPoint::new(0.0, 10.0)?, 0.5);
+        assert!(mid.lat().abs() < 1e-9 && (mid.lon() - 5.0).abs() < 1e-9);
+
+        let mid = origin.interpolate(GeoPoint::new(90.0, 0.0)?, 0.5);
+        assert!((mid.lat() - 45.0).abs() < 1e-9);
+
+        let mid = GeoPoint::new(60.0, 0.0)?.interpolate(GeoPoint::new(60.0, 180.0)?, 0.5);
+        assert!((mid.lat() - 90.0).abs() < 1e-6);
+
+        let end = origin.interpolate(GeoPoint::new(10.0, 20.0)?, 1.0);
+        assert!((end.lat() - 10.0).abs() < 1e-9 && (end.lon() - 20.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    /// Tests parsing distances with units
+    #[test]
+    fn test_distance_from_str() {
+        assert_eq!("10km".parse(), Ok(Distance::from_km(10.0)));
+        assert_eq!("500 m".parse(), Ok(Distance::from_meters(500.0)));
+        assert_eq!("2.5".parse(), Ok(Distance::from_km(2.5)));
+        assert_eq!("1nmi".parse(), Ok(Distance::from_meters(1852.0)));
+        assert!("ten km".parse::<Distance>().is_err());
+        assert!("10 furlongs".parse::<Distance>().is_err());
+    }
+
+    /// Tests the Web Mercator domain checks and their error messages
+    #[test]
+    fn test_mercator_validated() {
+        let limit = MERCATOR_LIMIT_M;
+        assert!(MercatorPoint::validated(limit, -limit, DomainPolicy::Reject).is_ok());
+
+        let err = MercatorPoint::validated(0.0, 3e7, DomainPolicy::Reject).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("y = 30000000 m is beyond the Web Mercator limit")
+        );
+        let err = MercatorPoint::validated(f64::NAN, 0.0, DomainPolicy::Clamp).unwrap_err();
+        assert_eq!(err.to_string(), "x = NaN is not a finite number");
+        assert!(MercatorPoint::validated(0.0, f64::NEG_INFINITY, DomainPolicy::Clamp).is_err());
+
+        let clamped = MercatorPoint::validated(-5e7, 3e7, DomainPolicy::Clamp).unwrap();
+        assert_eq!(clamped, MercatorPoint::new(-limit, limit));
+        assert!((clamped.to_wgs84().lat() - MERCATOR_MAX_LAT).abs() < 1e-9);
+    }
+
+    /// Tests distances at the numerically hard ends of the range
+    #[test]
+    fn test_great_circle_distance_edge_cases() -> Result<()> {
+        let half_circumference = PI * EARTH_MEAN_RADIUS_KM;
+
+        // Antipodal points, including both poles and across the antimeridian
+        let pairs = [
+            ((0.0, 0.0), (0.0, 180.0)),
+            ((90.0, 0.0), (-90.0, 0.0)),
+            ((45.0, 10.0), (-45.0, -170.0)),
+        ];
+        for ((lat1, lon1), (lat2, lon2)) in pairs {
+            let d = GeoPoint::new(lat1, lon1)?.great_circle_distance(GeoPoint::new(lat2, lon2)?);
+            assert!((d.km() - half_circumference).abs() < 1e-6);
+        }
+
+        // One millimeter apart along the equator
+        let a = GeoPoint::new(0.0, 0.0)?;
+        let b = GeoPoint::new(0.0, (1e-6 / EARTH_MEAN_RADIUS_KM).to_degrees())?;
+        assert!((a.great_circle_distance(b).km() - 1e-6).abs() < 1e-12);
+
+        Ok(())
+    }
+
+    /// Tests that the checked constructor rejects impossible coordinates
+    #[test]
+    fn test_geo_point_new_rejects_out_of_range() {
+        assert!(GeoPoint::new(90.0, 180.0).is_ok());
+        assert!(GeoPoint::new(90.1, 0.0).is_err());
+        assert!(GeoPoint::new(-91.0, 0.0).is_err());
+        assert!(GeoPoint::new(f64::NAN, 0.0).is_err());
+        assert!(GeoPoint::new(0.0, f64::INFINITY).is_err());
+    }
+
+    /// Tests the unit conversions of Distance
+    #[test]
+    fn test_distance_units() {
+        let distance = Distance::from_km(1.852);
+        assert!((distance.km() - 1.852).abs() < 1e-12);
+        assert!((distance.nautical_miles() - 1.0).abs() < 1e-12);
+        assert!((Distance::from_meters(1609.344).miles() - 1.0).abs() < 1e-12);
+        assert_eq!(distance.in_unit(Unit::M), 1852.0);
+        assert_eq!(Unit::Nmi.symbol(), "nmi");
+
+        let total: Distance = [Distance::from_km(1.0), Distance::from_km(2.0)]
+            .into_iter()
+            .sum();
+        assert_eq!(total * 2.0, Distance::from_km(6.0));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Any position: latitudes over the whole [-90°, 90°] range
+        fn geo_point() -> impl Strategy<Value = GeoPoint> {
+            (-90.0..=90.0f64, -180.0..=180.0f64)
+                .prop_map(|(lat, lon)| GeoPoint::new(lat, lon).unwrap())
+        }
+
+        proptest! {
+            /// Every point of the square converts to a finite position inside
+            /// the Mercator latitudes, and back to the same point
+            #[test]
+            fn mercator_round_trip(
+                x in -MERCATOR_LIMIT_M..=MERCATOR_LIMIT_M,
+                y in -MERCATOR_LIMIT_M..=MERCATOR_LIMIT_M,
+            ) {
+                let point = MercatorPoint::validated(x, y, DomainPolicy::Reject).unwrap();
+                let geo = point.to_wgs84();
+                prop_assert!(geo.lat().abs() <= MERCATOR_MAX_LAT + 1e-9);
+                prop_assert!(geo.lon().abs() <= 180.0 + 1e-9);
+
+                let back = GeoPoint::new(geo.lat(), geo.lon()).unwrap().to_mercator();
+                prop_assert!((back.x - x).abs() < 1e-6 && (back.y - y).abs() < 1e-6);
+            }
+
+            /// Any f64, NaN and infinities included: rejected or inside the square
+            #[test]
+            fn validated_stays_in_domain(x in any::<f64>(), y in any::<f64>()) {
+                for policy in [DomainPolicy::Reject, DomainPolicy::Clamp] {
+                    match MercatorPoint::validated(x, y, policy) {
+                        Ok(point) => {
+                            prop_assert!(point.x.abs() <= MERCATOR_LIMIT_M);
+                            prop_assert!(point.y.abs() <= MERCATOR_LIMIT_M);
+                            let geo = point.to_wgs84();
+                            prop_assert!(geo.lat().is_finite() && geo.lon().is_finite());
+                        }
+                        Err(_) => prop_assert!(
+                            !x.is_finite()
+                                || !y.is_finite()
+                                || (policy == DomainPolicy::Reject
+                                    && (x.abs() > MERCATOR_LIMIT_M || y.abs() > MERCATOR_LIMIT_M))
+                        ),
+                    }
+                }
+            }
+
+            /// Poles included, to_mercator always gives a point of the square
+            #[test]
+            fn to_mercator_is_finite(point in geo_point()) {
+                let mercator = point.to_mercator();
+                prop_assert!(mercator.y.abs() <= MERCATOR_LIMIT_M * (1.0 + 1e-12));
+                prop_assert!(mercator.x.is_finite());
+            }
+
+            /// Distances are finite, symmetric and within half a great circle
+            #[test]
+            fn distance_is_a_bounded_metric(a in geo_point(), b in geo_point(), c in geo_point()) {
+                let ab = a.great_circle_distance(b).km();
+                prop_assert!(ab.is_finite() && ab >= 0.0);
+                prop_assert!(ab <= PI * EARTH_MEAN_RADIUS_KM + 1e-9);
+                prop_assert!((ab - b.great_circle_distance(a).km()).abs() < 1e-9);
+                prop_assert!(a.great_circle_distance(a).km() == 0.0);
+
+                let ac = a.great_circle_distance(c).km();
+                let cb = c.great_circle_distance(b).km();
+                prop_assert!(ab <= ac + cb + 1e-6);
+            }
+
+            /// The antipode is always half a great circle away
+            #[test]
+            fn antipode_distance(point in geo_point()) {
+                let antipode = GeoPoint::new(-point.lat(), point.lon() + 180.0).unwrap();
+                let d = point.great_circle_distance(antipode).km();
+                prop_assert!((d - PI * EARTH_MEAN_RADIUS_KM).abs() < 1e-6);
+            }
+
+            /// Nearly identical points: the distance matches the tiny offset
+            #[test]
+            fn nearby_distance(point in geo_point(), offset_m in 1e-3..10.0f64) {
+                prop_assume!(point.lat().abs() < 89.0);
+                // Move north by offset_m along the meridian
+                let dlat = (offset_m / 1000.0 / EARTH_MEAN_RADIUS_KM).to_degrees();
+                let moved = GeoPoint::new(point.lat() + dlat, point.lon()).unwrap();
+                let d = point.great_circle_distance(moved).km() * 1000.0;
+                prop_assert!((d - offset_m).abs() < 1e-6, "{} m instead of {} m", d, offset_m);
+            }
+        }
+    }
+}
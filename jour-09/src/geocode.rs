@@ -0,0 +1,133 @@
+use eyre::{Context, Result};
+use std::fs;
+
+use crate::geo::{Distance, GeoPoint};
+use crate::spatial::SpatialIndex;
+
+/// A named place of the gazetteer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub name: String,
+    pub position: GeoPoint,
+}
+
+/// Offline reverse geocoder: a list of places and a spatial index over them
+///
+/// The index ids are the positions of the places in `places`.
+pub struct Gazetteer {
+    places: Vec<Place>,
+    index: SpatialIndex,
+}
+
+impl Gazetteer {
+    /// Indexes the given places
+    pub fn new(places: Vec<Place>) -> Self {
+        let index = SpatialIndex::from_positions(
+            places
+                .iter()
+                .zip(0..)
+                .map(|(place, id)| (id, place.position)),
+        );
+        Self { places, index }
+    }
+
+    /// Loads a gazetteer file: one "name,lat,lon" per line
+    ///
+    /// Names may contain commas, the coordinates are the last two fields.
+    /// Blank lines, `#` comments and a leading "name,lat,lon" header are skipped.
+    pub fn load(file_path: &str) -> Result<Self> {
+        let content = fs::read_to_string(file_path)
+            .wrap_err_with(|| format!("Failed to read gazetteer '{}'", file_path))?;
+
+        let mut places = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.rsplitn(3, ',').map(str::trim);
+            let (Some(lon), Some(lat), Some(name)) = (fields.next(), fields.next(), fields.next())
+            else {
+                eyre::bail!(
+                    "line {}: expected 'name,lat,lon', found '{}'",
+                    index + 1,
+                    line
+                );
+            };
+            if places.is_empty() && lat.eq_ignore_ascii_case("lat") {
+                continue;
+            }
+
+            let parse = |raw: &str| {
+                raw.parse::<f64>()
+                    .wrap_err_with(|| format!("line {}: invalid coordinate '{}'", index + 1, raw))
+            };
+            let position = GeoPoint::new(parse(lat)?, parse(lon)?)
+                .wrap_err_with(|| format!("line {}: invalid position", index + 1))?;
+            places.push(Place {
+                name: name.to_string(),
+                position,
+            });
+        }
+
+        if places.is_empty() {
+            eyre::bail!("Gazetteer '{}' holds no places", file_path);
+        }
+
+        Ok(Self::new(places))
+    }
+
+    /// Closest place to a position, with its great-circle distance
+    pub fn nearest(&self, position: GeoPoint) -> Option<(&Place, Distance)> {
+        let neighbour = self.index.nearest(position, 1).pop()?;
+        Some((&self.places[neighbour.id as usize], neighbour.distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    /// Tests loading a file with a header, comments and a comma in a name
+    #[test]
+    fn test_load_and_nearest() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "name,lat,lon")?;
+        writeln!(file, "# Lapland")?;
+        writeln!(file, "Rovaniemi, Finland,66.5039,25.7294")?;
+        writeln!(file)?;
+        writeln!(file, "Paris,48.8566,2.3522")?;
+
+        let gazetteer = Gazetteer::load(file.path().to_str().unwrap())?;
+
+        let (place, distance) = gazetteer.nearest(GeoPoint::new(66.55, 25.85)?).unwrap();
+        assert_eq!(place.name, "Rovaniemi, Finland");
+        assert!(distance.km() < 10.0);
+
+        let (place, _) = gazetteer.nearest(GeoPoint::new(45.76, 4.84)?).unwrap();
+        assert_eq!(place.name, "Paris");
+
+        Ok(())
+    }
+
+    /// Tests that errors name the line
+    #[test]
+    fn test_load_errors() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "Paris,48.8566,2.3522")?;
+        writeln!(file, "Nowhere,91.0,0.0")?;
+
+        let err = Gazetteer::load(file.path().to_str().unwrap())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("line 2: invalid position"));
+
+        let empty = NamedTempFile::new()?;
+        assert!(Gazetteer::load(empty.path().to_str().unwrap()).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use eyre::{Context, Result};
+use serde_json::Value;
+use std::fs;
+
+use crate::geo::{Distance, GeoPoint, MERCATOR_MAX_LAT, MercatorPoint, TracePoint};
+
+/// A ring of Web Mercator vertices, implicitly closed
+type Ring = Vec<MercatorPoint>;
+
+/// A polygon made of an outer ring and optional holes
+#[derive(Debug, Clone)]
+struct Polygon {
+    outer: Ring,
+    holes: Vec<Ring>,
+}
+
+/// A named restricted zone, possibly made of several polygons
+#[derive(Debug, Clone)]
+pub struct Zone {
+    pub name: String,
+    polygons: Vec<Polygon>,
+}
+
+impl Zone {
+    /// Tests whether a Web Mercator point lies inside the zone
+    ///
+    /// Web Mercator is conformal and maps meridians and parallels to straight
+    /// lines, so the planar ray-casting test is exact for zones whose edges are
+    /// drawn as straight lines on a web map (the usual way they are authored).
+    pub fn contains(&self, point: MercatorPoint) -> bool {
+        self.polygons.iter().any(|polygon| {
+            ring_contains(&polygon.outer, point)
+                && !polygon.holes.iter().any(|hole| ring_contains(hole, point))
+        })
+    }
+
+    /// Fraction of the straight Mercator segment from `a` to `b` lying inside
+    /// the zone
+    ///
+    /// The segment is cut wherever it crosses an edge of a ring (outer rings
+    /// and holes alike); each piece is then wholly inside or outside, which
+    /// its midpoint tells.
+    fn fraction_inside(&self, a: MercatorPoint, b: MercatorPoint) -> f64 {
+        let mut cuts = vec![0.0, 1.0];
+        for ring in self
+            .polygons
+            .iter()
+            .flat_map(|polygon| std::iter::once(&polygon.outer).chain(&polygon.holes))
+        {
+            let edges = ring.iter().zip(ring.iter().cycle().skip(1));
+            cuts.extend(edges.filter_map(|(&r, &s)| segment_intersection(a, b, r, s)));
+        }
+        cuts.sort_by(f64::total_cmp);
+
+        cuts.windows(2)
+            .filter(|w| {
+                let t = (w[0] + w[1]) / 2.0;
+                self.contains(MercatorPoint::new(
+                    a.x + (b.x - a.x) * t,
+                    a.y + (b.y - a.y) * t,
+                ))
+            })
+            .map(|w| w[1] - w[0])
+            .sum()
+    }
+}
+
+/// Position along `a`→`b` (0 at `a`, 1 at `b`) where it crosses the edge `r`→`s`
+///
+/// Parallel segments never cross: touching along an edge does not split the
+/// segment into inside and outside pieces.
+fn segment_intersection(
+    a: MercatorPoint,
+    b: MercatorPoint,
+    r: MercatorPoint,
+    s: MercatorPoint,
+) -> Option<f64> {
+    let cross = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| x1 * y2 - y1 * x2;
+    let d = (b.x - a.x, b.y - a.y);
+    let e = (s.x - r.x, s.y - r.y);
+    let ar = (r.x - a.x, r.y - a.y);
+
+    let denominator = cross(d, e);
+    if denominator == 0.0 {
+        return None;
+    }
+    let t = cross(ar, e) / denominator;
+    let u = cross(ar, d) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+/// Whether the sleigh entered or left a zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossing {
+    Entry,
+    Exit,
+}
+
+/// A zone boundary crossing, at the first point on the new side
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneEvent {
+    pub zone: String,
+    pub crossing: Crossing,
+    pub id: i32,
+}
+
+/// Time (in id units) and distance spent inside a zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneSummary {
+    pub zone: String,
+    pub id_units: f64,
+    pub distance: Distance,
+}
+
+/// Even-odd ray casting: counts the edges crossed by a ray going east from the point
+fn ring_contains(ring: &[MercatorPoint], point: MercatorPoint) -> bool {
+    let MercatorPoint { x, y } = point;
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+
+    for i in 0..ring.len() {
+        let MercatorPoint { x: xi, y: yi } = ring[i];
+        let MercatorPoint { x: xj, y: yj } = ring[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+
+    inside
+}
+
+/// Loads the zones of a GeoJSON file
+///
+/// Accepts a FeatureCollection, a single Feature or a bare geometry. Polygon
+/// and MultiPolygon geometries are kept, the zone name comes from the `name`
+/// property (or "zone N" when absent).
+pub fn load_zones(file_path: &str) -> Result<Vec<Zone>> {
+    let content = fs::read_to_string(file_path)
+        .wrap_err_with(|| format!("Failed to read zones '{}'", file_path))?;
+    let json: Value = serde_json::from_str(&content)
+        .wrap_err_with(|| format!("Invalid GeoJSON in '{}'", file_path))?;
+
+    let features: Vec<&Value> = match json["type"].as_str() {
+        Some("FeatureCollection") => json["features"]
+            .as_array()
+            .ok_or_else(|| eyre::eyre!("FeatureCollection without 'features' array"))?
+            .iter()
+            .collect(),
+        _ => vec![&json],
+    };
+
+    let mut zones = Vec::new();
+    for (index, feature) in features.into_iter().enumerate() {
+        // A bare geometry is its own geometry
+        let geometry = if feature["type"] == "Feature" {
+            &feature["geometry"]
+        } else {
+            feature
+        };
+        let name = feature["properties"]["name"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("zone {}", index + 1));
+
+        let polygons = match geometry["type"].as_str() {
+            Some("Polygon") => vec![parse_polygon(&geometry["coordinates"])?],
+            Some("MultiPolygon") => geometry["coordinates"]
+                .as_array()
+                .ok_or_else(|| eyre::eyre!("{}: MultiPolygon without coordinates", name))?
+                .iter()
+                .map(parse_polygon)
+                .collect::<Result<_>>()?,
+            // Points and lines cannot contain anything
+            _ => continue,
+        };
+
+        zones.push(Zone { name, polygons });
+    }
+
+    Ok(zones)
+}
+
+/// Parses GeoJSON polygon coordinates: [outer ring, hole, hole, ...]
+fn parse_polygon(coordinates: &Value) -> Result<Polygon> {
+    let mut rings = coordinates
+        .as_array()
+        .ok_or_else(|| eyre::eyre!("Polygon coordinates must be an array of rings"))?
+        .iter()
+        .map(parse_ring);
+
+    let outer = rings
+        .next()
+        .ok_or_else(|| eyre::eyre!("Polygon without outer ring"))??;
+    let holes = rings.collect::<Result<_>>()?;
+
+    Ok(Polygon { outer, holes })
+}
+
+/// Parses a ring of [lon, lat] positions and projects it to Web Mercator
+///
+/// Zones are tested in the Web Mercator plane, which stops at ±85.05°: a ring
+/// reaching beyond would be silently flattened onto that edge, so polar zones
+/// are refused.
+fn parse_ring(ring: &Value) -> Result<Ring> {
+    ring.as_array()
+        .ok_or_else(|| eyre::eyre!("Ring must be an array of positions"))?
+        .iter()
+        .map(
+            |position| match (position[0].as_f64(), position[1].as_f64()) {
+                (Some(_), Some(lat)) if lat.abs() > MERCATOR_MAX_LAT => Err(eyre::eyre!(
+                    "Position {} is beyond the Web Mercator latitude limit of ±{:.2}°, \
+                     polar zones are not supported",
+                    position,
+                    MERCATOR_MAX_LAT
+                )),
+                (Some(lon), Some(lat)) => Ok(GeoPoint::new(lat, lon)?.to_mercator()),
+                _ => Err(eyre::eyre!("Invalid position {}", position)),
+            },
+        )
+        .collect()
+}
+
+/// Walks the sorted trace through every zone
+///
+/// Output: the entry/exit events in trace order, and per zone the time (id
+/// units) and great-circle distance spent inside. A segment crossing the
+/// boundary is split where it meets the rings, and counts for the share of
+/// its length inside the zone (time is assumed to flow evenly along it).
+pub fn evaluate(trace: &[TracePoint], zones: &[Zone]) -> (Vec<ZoneEvent>, Vec<ZoneSummary>) {
+    let mut events = Vec::new();
+    let mut summaries = Vec::new();
+
+    for zone in zones {
+        let inside: Vec<bool> = trace.iter().map(|p| zone.contains(p.position)).collect();
+        let mut summary = ZoneSummary {
+            zone: zone.name.clone(),
+            id_units: 0.0,
+            distance: Distance::default(),
+        };
+
+        // A trace starting inside the zone enters it at its first point
+        if let (Some(&true), Some(first)) = (inside.first(), trace.first()) {
+            events.push(ZoneEvent {
+                zone: zone.name.clone(),
+                crossing: Crossing::Entry,
+                id: first.id,
+            });
+        }
+
+        for (i, pair) in trace.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+
+            if inside[i] != inside[i + 1] {
+                events.push(ZoneEvent {
+                    zone: zone.name.clone(),
+                    crossing: if inside[i] {
+                        Crossing::Exit
+                    } else {
+                        Crossing::Entry
+                    },
+                    id: b.id,
+                });
+            }
+
+            // Even with both ends on the same side, the segment may cut
+            // through a corner of the zone or a hole
+            let weight = zone.fraction_inside(a.position, b.position);
+            if weight > 0.0 {
+                summary.id_units += weight * (i64::from(b.id) - i64::from(a.id)) as f64;
+                summary.distance += a.geo().great_circle_distance(b.geo()) * weight;
+            }
+        }
+
+        summaries.push(summary);
+    }
+
+    // Interleave the events of all zones in trace order
+    events.sort_by_key(|event| event.id);
+
+    (events, summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    /// A 10°x10° square zone around (0°, 0°) with a 2°x2° hole in the middle
+    const ZONES: &str = r#"{
+        "type": "FeatureCollection",
+        "features": [{
+            "type": "Feature",
+            "properties": { "name": "North Pole Airspace" },
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [
+                    [[-5, -5], [5, -5], [5, 5], [-5, 5], [-5, -5]],
+                    [[-1, -1], [1, -1], [1, 1], [-1, 1], [-1, -1]]
+                ]
+            }
+        }]
+    }"#;
+
+    fn zones() -> Result<Vec<Zone>> {
+        let mut file = NamedTempFile::new()?;
+        write!(file, "{}", ZONES)?;
+        load_zones(file.path().to_str().unwrap())
+    }
+
+    /// Tests loading and point-in-polygon with a hole
+    #[test]
+    fn test_zone_contains() -> Result<()> {
+        let zones = zones()?;
+        assert_eq!(zones.len(), 1);
+        assert_eq!(zones[0].name, "North Pole Airspace");
+
+        let inside = |lat, lon| -> Result<bool> {
+            Ok(zones[0].contains(GeoPoint::new(lat, lon)?.to_mercator()))
+        };
+        assert!(inside(3.0, 3.0)?);
+        assert!(!inside(0.0, 0.0)?); // in the hole
+        assert!(!inside(0.0, 6.0)?);
+
+        Ok(())
+    }
+
+    /// Tests entry/exit events and the time and distance spent inside
+    ///
+    /// The trace walks east along the equator, through the hole: ids 1 and 4
+    /// are outside, ids 2 and 3 inside.
+    #[test]
+    fn test_evaluate() -> Result<()> {
+        let trace = [(1, -9.0), (2, -3.0), (3, 3.0), (4, 7.0)]
+            .iter()
+            .map(|&(id, lon)| {
+                Ok(TracePoint {
+                    id,
+                    position: GeoPoint::new(0.0, lon)?.to_mercator(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (events, summaries) = evaluate(&trace, &zones()?);
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.crossing, e.id))
+                .collect::<Vec<_>>(),
+            vec![(Crossing::Entry, 2), (Crossing::Exit, 4)]
+        );
+
+        // 2° of the 6° of 1→2, 2→3 but the 2° of the hole, 2° of the 4° of
+        // 3→4: 1/3 + 2/3 + 1/2 = 1.5 id units, 2° + 4° + 2° = 8° of longitude
+        // along the equator ≈ 890 km
+        assert!((summaries[0].id_units - 1.5).abs() < 1e-9);
+        assert!((summaries[0].distance.km() - 889.6).abs() < 1.0);
+
+        Ok(())
+    }
+
+    /// Tests that a segment with both ends outside still counts the corner
+    /// it cuts through
+    #[test]
+    fn test_fraction_inside_corner() -> Result<()> {
+        let zone = &zones()?[0];
+        let point = |lat, lon| GeoPoint::new(lat, lon).map(GeoPoint::to_mercator);
+
+        // Along the equator the Mercator x is linear in the longitude
+        let across = zone.fraction_inside(point(0.0, -10.0)?, point(0.0, 10.0)?);
+        assert!((across - 8.0 / 20.0).abs() < 1e-9);
+        assert_eq!(
+            zone.fraction_inside(point(20.0, 0.0)?, point(30.0, 0.0)?),
+            0.0
+        );
+        assert!((zone.fraction_inside(point(3.0, 2.0)?, point(3.0, 4.0)?) - 1.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    /// Tests that zones reaching beyond the Web Mercator limit are refused
+    #[test]
+    fn test_polar_zone_rejected() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        write!(
+            file,
+            r#"{{"type": "Polygon", "coordinates": [[[0, 80], [90, 80], [180, 89], [0, 80]]]}}"#
+        )?;
+        let err = load_zones(file.path().to_str().unwrap()).unwrap_err();
+        assert!(format!("{:#}", err).contains("polar zones are not supported"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+//! Analyses of the sleigh trace behind the `jour-09` command-line tool
+//!
+//! The modules live in a library so that the benchmarks in `benches/` can
+//! run them on the real trace.
+
+use eyre::{Context, Result};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+pub mod cli;
+pub mod cluster;
+pub mod compare;
+pub mod densify;
+pub mod extsort;
+pub mod fleet;
+pub mod geo;
+pub mod geocode;
+pub mod geofence;
+pub mod output;
+pub mod plot;
+pub mod reader;
+pub mod segments;
+pub mod spatial;
+pub mod svg;
+pub mod validation;
+
+#[cfg(test)]
+mod testing;
+
+use crate::geo::TracePoint;
+use crate::reader::{ReaderConfig, Record};
+use crate::validation::{StreamValidator, Trace, ValidationPolicy, ValidationReport};
+
+/// Path standing for the standard input
+const STDIN: &str = "-";
+
+/// Opens the input trace: a file, or the standard input for "-"
+pub fn open_input(file_path: &str) -> Result<Box<dyn BufRead>> {
+    if file_path == STDIN {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    // Open the file and wrap any error with context
+    let file =
+        File::open(file_path).wrap_err_with(|| format!("Failed to open file '{}'", file_path))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Reads and parses CSV data from file (or "-" for stdin), in file order
+/// Format: id,x_coordinate,y_coordinate (one per line), or any layout
+/// described by `config` (header, delimiter, trimming, comments, sleigh column)
+pub fn read_file(file_path: &str, config: &ReaderConfig) -> Result<Vec<Record>> {
+    // Parse each line into a record
+    reader::read_records(open_input(file_path)?, config)
+        .wrap_err_with(|| format!("Failed to parse '{}'", file_path))
+}
+
+/// Reads and parses CSV data from file, returning sorted trace points
+/// Returns Vec sorted by the first field (id), after duplicate ids and gaps
+/// in the sequence have been handled according to `policy`, along with the
+/// data-quality issues found
+/// Fails if the file holds the traces of several sleighs
+pub fn parse_and_sort(
+    file_path: &str,
+    config: &ReaderConfig,
+    policy: &ValidationPolicy,
+) -> Result<(Trace, ValidationReport)> {
+    let records = read_file(file_path, config)?;
+
+    let mut sleighs: Vec<&str> = records.iter().filter_map(|r| r.sleigh.as_deref()).collect();
+    sleighs.sort_unstable();
+    sleighs.dedup();
+    if sleighs.len() > 1 {
+        eyre::bail!(
+            "'{}' holds the traces of {} sleighs ({}), use the 'fleet' subcommand",
+            file_path,
+            sleighs.len(),
+            sleighs.join(", ")
+        );
+    }
+
+    // Sort by id and apply the duplicate/gap policy
+    let records = records.into_iter().map(|r| (r.line, r.point)).collect();
+    validation::validate(records, policy)
+}
+
+/// Same as `parse_and_sort` for files larger than memory, but only keeps the
+/// first and last points of the sorted trace
+/// The records are sorted by an external merge sort (`run_size` records in
+/// memory at a time) and validated on the fly from the merged stream
+pub fn parse_and_sort_external(
+    file_path: &str,
+    config: &ReaderConfig,
+    policy: &ValidationPolicy,
+    run_size: usize,
+) -> Result<(Trace, ValidationReport)> {
+    let records = reader::records(open_input(file_path)?, config);
+    let merged = extsort::external_sort(records, run_size)
+        .wrap_err_with(|| format!("Failed to parse '{}'", file_path))?;
+
+    let mut ends: Trace = Vec::with_capacity(2);
+    let mut keep_ends = |point: TracePoint| match ends.as_mut_slice() {
+        [] | [_] => ends.push(point),
+        [_, last] => *last = point,
+        _ => unreachable!("at most two points are kept"),
+    };
+
+    let mut validator = StreamValidator::new(*policy);
+    for record in merged {
+        let (line, point) = record?;
+        validator.push(line, point, &mut keep_ends);
+    }
+    let report = validator.finish(&mut keep_ends)?;
+
+    Ok((ends, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    /// Tests that parse_and_sort correctly reads CSV data and sorts by id
+    ///
+    /// Creates a temporary file with three data points in unsorted order (3, 1, 2)
+    /// and verifies that they are sorted correctly (1, 2, 3) and that the
+    /// coordinate values are preserved accurately.
+    #[test]
+    fn test_parse_and_sort() -> Result<()> {
+        // Create a temporary file with unsorted data
+        let mut file = NamedTempFile::new()?;
+
+        writeln!(file, "3,1000.0,2000.0")?; // Third point
+        writeln!(file, "1,3000.0,4000.0")?; // First point
+        writeln!(file, "2,2000.0,3000.0")?; // Second point
+
+        let path = file.path().to_str().unwrap();
+        let (data, _) =
+            parse_and_sort(path, &ReaderConfig::default(), &ValidationPolicy::default())?;
+
+        // Verify correct sorting by id
+        assert_eq!(data[0].id, 1);
+        assert_eq!(data[1].id, 2);
+        assert_eq!(data[2].id, 3);
+
+        // Verify that coordinates are correctly associated with id=1
+        assert_eq!(data[0].position.x, 3000.0); // x coordinate
+        assert_eq!(data[0].position.y, 4000.0); // y coordinate
+
+        Ok(())
+    }
+
+    /// Tests that parse_and_sort reports duplicate ids with their line numbers
+    ///
+    /// Id 2 appears on lines 2 and 3: the default policy must reject the
+    /// trace, while the "last" policy keeps the point from line 3.
+    #[test]
+    fn test_parse_and_sort_duplicates() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+
+        writeln!(file, "1,0.0,0.0")?;
+        writeln!(file, "2,1000.0,1000.0")?;
+        writeln!(file, "2,2000.0,2000.0")?;
+
+        let path = file.path().to_str().unwrap();
+
+        let err = parse_and_sort(path, &ReaderConfig::default(), &ValidationPolicy::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("lines 2 and 3"));
+
+        let policy = ValidationPolicy {
+            duplicates: validation::DuplicatePolicy::Last,
+            ..Default::default()
+        };
+        let (data, report) = parse_and_sort(path, &ReaderConfig::default(), &policy)?;
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(
+            data,
+            vec![
+                TracePoint::new(1, 0.0, 0.0),
+                TracePoint::new(2, 2000.0, 2000.0)
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// Tests that parse errors name the file, the line and the raw value
+    #[test]
+    fn test_parse_and_sort_invalid_value() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+
+        writeln!(file, "1,0.0,0.0")?;
+        writeln!(file, "2,1000.0,north")?;
+
+        let path = file.path().to_str().unwrap();
+        let err = parse_and_sort(path, &ReaderConfig::default(), &ValidationPolicy::default())
+            .unwrap_err();
+
+        // The context names the file, the root cause names the line and value
+        assert!(format!("{:#}", err).contains("line 2, column 'y': invalid value 'north'"));
+
+        Ok(())
+    }
+
+    /// Tests that a fleet log is refused as a single trace, unless it only
+    /// holds one sleigh
+    #[test]
+    fn test_parse_and_sort_fleet() -> Result<()> {
+        let config = ReaderConfig {
+            fleet: true,
+            ..Default::default()
+        };
+
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "comet,1,0.0,0.0")?;
+        writeln!(file, "comet,2,1000.0,0.0")?;
+        let path = file.path().to_str().unwrap().to_string();
+        let (data, _) = parse_and_sort(&path, &config, &ValidationPolicy::default())?;
+        assert_eq!(data.len(), 2);
+
+        writeln!(file, "dasher,1,0.0,0.0")?;
+        let err = parse_and_sort(&path, &config, &ValidationPolicy::default()).unwrap_err();
+        assert!(err.to_string().contains("2 sleighs (comet, dasher)"));
+
+        Ok(())
+    }
+
+    /// Tests that the external sort gives the same ends and report as the
+    /// in-memory path, with runs much smaller than the file
+    #[test]
+    fn test_parse_and_sort_external_matches() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        for (id, x) in [
+            (9, 1.0),
+            (3, 2.0),
+            (7, 3.0),
+            (1, 4.0),
+            (3, 5.0),
+            (12, 6.0),
+            (2, 7.0),
+        ] {
+            writeln!(file, "{},{},{}", id, x * 1000.0, -x * 500.0)?;
+        }
+        let path = file.path().to_str().unwrap();
+
+        for duplicates in [
+            validation::DuplicatePolicy::First,
+            validation::DuplicatePolicy::Last,
+        ] {
+            for gaps in [
+                validation::GapPolicy::Keep,
+                validation::GapPolicy::Interpolate,
+            ] {
+                let policy = ValidationPolicy { duplicates, gaps };
+                let (data, report) = parse_and_sort(path, &ReaderConfig::default(), &policy)?;
+                let (ends, external_report) =
+                    parse_and_sort_external(path, &ReaderConfig::default(), &policy, 2)?;
+
+                assert_eq!(ends, vec![data[0], data[data.len() - 1]]);
+                assert_eq!(external_report, report);
+            }
+        }
+
+        // The default policy rejects the duplicate id 3 the same way
+        let err = parse_and_sort_external(path, &ReaderConfig::default(), &Default::default(), 2)
+            .unwrap_err();
+        assert!(err.to_string().contains("duplicate id 3 at lines 2 and 5"));
+
+        Ok(())
+    }
+
+    /// Tests handling of empty files
+    ///
+    /// Creates an empty temporary file and verifies that parse_and_sort
+    /// returns an empty vector rather than failing or panicking.
+    #[test]
+    fn test_parse_and_sort_empty_file() -> Result<()> {
+        // Create an empty temporary file
+        let file = NamedTempFile::new()?;
+        let path = file.path().to_str().unwrap();
+        let (data, _) =
+            parse_and_sort(path, &ReaderConfig::default(), &ValidationPolicy::default())?;
+
+        // Should return empty vector, not error
+        assert!(data.is_empty());
+
+        Ok(())
+    }
+}
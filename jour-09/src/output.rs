@@ -0,0 +1,399 @@
+use eyre::Result;
+use serde_json::{Value, json};
+
+use crate::cluster::Clustering;
+use crate::compare::{Comparison, PointPair};
+use crate::fleet::{self, SleighTrace};
+use crate::geo::{Distance, TracePoint, Unit};
+use crate::geocode::Gazetteer;
+use crate::geofence::{Crossing, ZoneEvent, ZoneSummary};
+use crate::segments::SegmentReport;
+use crate::spatial::Neighbour;
+
+/// Most decimals worth printing: an f64 holds about 15 significant digits
+pub const MAX_PRECISION: u8 = 15;
+
+/// Prints the reports as text or JSON, with distances in the chosen unit
+///
+/// Distances are rounded to `precision` decimals (at most `MAX_PRECISION`,
+/// checked by the command line) in both formats. JSON documents are printed
+/// on standard output, one per run, and always carry the unit; warnings keep
+/// going to standard error.
+pub struct Printer {
+    pub unit: Unit,
+    pub precision: usize,
+    pub json: bool,
+}
+
+impl Printer {
+    /// A distance followed by its unit, for text output
+    fn text(&self, distance: Distance) -> String {
+        format!(
+            "{:.*} {}",
+            self.precision,
+            distance.in_unit(self.unit),
+            self.unit.symbol()
+        )
+    }
+
+    /// A distance as a rounded JSON number
+    fn number(&self, distance: Distance) -> Value {
+        let factor = 10f64.powi(self.precision as i32);
+        json!((distance.in_unit(self.unit) * factor).round() / factor)
+    }
+
+    fn emit(&self, mut document: Value) {
+        document["unit"] = json!(self.unit.symbol());
+        println!("{:#}", document);
+    }
+
+    /// Great-circle distance between the first and last point of the trace
+    pub fn distance(&self, sorted_data: &[TracePoint]) -> Result<()> {
+        // Get the first point (earliest in sequence) and the last (latest)
+        let (first_point, last_point) = sorted_data
+            .first()
+            .zip(sorted_data.last())
+            .ok_or(eyre::eyre!("No data points found"))?;
+
+        // Convert points from Web Mercator to WGS84 and calculate the
+        // great-circle distance between the two points
+        let distance = first_point.geo().great_circle_distance(last_point.geo());
+
+        if self.json {
+            self.emit(json!({
+                "first_id": first_point.id,
+                "last_id": last_point.id,
+                "distance": self.number(distance),
+            }));
+        } else {
+            println!(
+                "Distance between the first and last point: {}",
+                self.text(distance)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// One line per sleigh, then the fleet totals
+    pub fn fleet(&self, traces: &[SleighTrace]) -> Result<()> {
+        if traces.is_empty() {
+            eyre::bail!("No data points found");
+        }
+
+        let mut summaries = Vec::with_capacity(traces.len());
+        for trace in traces {
+            if !trace.report.is_clean() {
+                eprintln!("Warning: sleigh {}: {}", trace.sleigh, trace.report);
+            }
+            summaries.push(trace.summary());
+        }
+        let total = fleet::fleet_total(&summaries);
+
+        if self.json {
+            let sleighs: Vec<Value> = summaries
+                .iter()
+                .map(|summary| {
+                    json!({
+                        "sleigh": summary.sleigh,
+                        "start_to_end": self.number(summary.start_to_end),
+                        "path_length": self.number(summary.path_length),
+                        "points": summary.points,
+                    })
+                })
+                .collect();
+            self.emit(json!({
+                "sleighs": sleighs,
+                "total": {
+                    "sleighs": total.sleighs,
+                    "start_to_end": self.number(total.start_to_end),
+                    "path_length": self.number(total.path_length),
+                    "points": total.points,
+                },
+            }));
+            return Ok(());
+        }
+
+        for summary in &summaries {
+            println!(
+                "{}: {} from start to end, {} travelled, {} points",
+                summary.sleigh,
+                self.text(summary.start_to_end),
+                self.text(summary.path_length),
+                summary.points
+            );
+        }
+        println!(
+            "Fleet ({} sleighs): {} from start to end, {} travelled, {} points",
+            total.sleighs,
+            self.text(total.start_to_end),
+            self.text(total.path_length),
+            total.points
+        );
+
+        Ok(())
+    }
+
+    /// Route distances and the most divergent point pairs
+    pub fn comparison(&self, comparison: &Comparison) {
+        if self.json {
+            let pair = |pair: &PointPair| {
+                json!({
+                    "planned_id": pair.planned_id,
+                    "actual_id": pair.actual_id,
+                    "distance": self.number(pair.distance),
+                })
+            };
+            self.emit(json!({
+                "frechet": pair(&comparison.frechet),
+                "hausdorff": pair(&comparison.hausdorff),
+                "divergences": comparison.divergences.iter().map(pair).collect::<Vec<_>>(),
+            }));
+            return;
+        }
+
+        let describe = |pair: &PointPair| {
+            format!(
+                "{} (planned id {}, actual id {})",
+                self.text(pair.distance),
+                pair.planned_id,
+                pair.actual_id
+            )
+        };
+        println!(
+            "Discrete Fréchet distance: {}",
+            describe(&comparison.frechet)
+        );
+        println!("Hausdorff distance: {}", describe(&comparison.hausdorff));
+        if !comparison.divergences.is_empty() {
+            println!("Largest divergences along the route:");
+            for pair in &comparison.divergences {
+                println!("  {}", describe(pair));
+            }
+        }
+    }
+
+    /// Segment table, total turning and the sharpest turns
+    pub fn segments(&self, report: &SegmentReport, sharpest: usize) {
+        let turns = report.sharpest_turns(sharpest);
+
+        if self.json {
+            let segments: Vec<Value> = report
+                .segments
+                .iter()
+                .map(|s| {
+                    json!({
+                        "from": s.from,
+                        "to": s.to,
+                        "initial_bearing": s.initial_bearing,
+                        "final_bearing": s.final_bearing,
+                        "turn": s.turn,
+                        "length": self.number(s.length),
+                    })
+                })
+                .collect();
+            let sharpest: Vec<Value> = turns
+                .iter()
+                .map(|&(id, angle)| json!({ "id": id, "angle": angle }))
+                .collect();
+            self.emit(json!({
+                "segments": segments,
+                "total_turning": report.total_turning(),
+                "net_turning": report.net_turning(),
+                "sharpest_turns": sharpest,
+            }));
+            return;
+        }
+
+        print!("{}", report.to_table(self.unit, self.precision));
+        println!(
+            "Total turning: {:.1}° (net {:.1}°, positive to the right)",
+            report.total_turning(),
+            report.net_turning()
+        );
+        for (id, angle) in turns {
+            let side = if angle < 0.0 { "left" } else { "right" };
+            println!("  id {}: {:.1}° {}", id, angle.abs(), side);
+        }
+    }
+
+    /// Zone entries and exits, then the time and distance spent in every zone
+    pub fn geofence(&self, events: &[ZoneEvent], summaries: &[ZoneSummary]) {
+        let verb = |event: &ZoneEvent| match event.crossing {
+            Crossing::Entry => "enters",
+            Crossing::Exit => "leaves",
+        };
+
+        if self.json {
+            let events: Vec<Value> = events
+                .iter()
+                .map(|event| json!({ "id": event.id, "zone": event.zone, "crossing": verb(event) }))
+                .collect();
+            let zones: Vec<Value> = summaries
+                .iter()
+                .map(|summary| {
+                    json!({
+                        "zone": summary.zone,
+                        "id_units": summary.id_units,
+                        "distance": self.number(summary.distance),
+                    })
+                })
+                .collect();
+            self.emit(json!({ "events": events, "zones": zones }));
+            return;
+        }
+
+        for event in events {
+            println!("id {}: {} {}", event.id, verb(event), event.zone);
+        }
+        for summary in summaries {
+            println!(
+                "{}: {:.1} id units, {} inside",
+                summary.zone,
+                summary.id_units,
+                self.text(summary.distance)
+            );
+        }
+    }
+
+    /// Places nearest to the start and end of the trace, and to every point
+    /// when `every` is set
+    pub fn places(
+        &self,
+        sorted_data: &[TracePoint],
+        gazetteer: &Gazetteer,
+        every: bool,
+    ) -> Result<()> {
+        let (first, last) = sorted_data
+            .first()
+            .zip(sorted_data.last())
+            .ok_or(eyre::eyre!("No data points found"))?;
+        let points: &[TracePoint] = if every { sorted_data } else { &[] };
+
+        if self.json {
+            let annotate = |point: &TracePoint| match gazetteer.nearest(point.geo()) {
+                Some((place, distance)) => json!({
+                    "id": point.id,
+                    "place": place.name,
+                    "distance": self.number(distance),
+                }),
+                None => json!({ "id": point.id, "place": null }),
+            };
+            let mut document = json!({ "start": annotate(first), "end": annotate(last) });
+            if every {
+                document["points"] = points.iter().map(annotate).collect();
+            }
+            self.emit(document);
+            return Ok(());
+        }
+
+        let describe = |point: &TracePoint| match gazetteer.nearest(point.geo()) {
+            Some((place, distance)) => format!("near {} ({})", place.name, self.text(distance)),
+            None => "nowhere known".to_string(),
+        };
+        for point in points {
+            println!("id {}: {}", point.id, describe(point));
+        }
+        println!("Started {}, ended {}", describe(first), describe(last));
+
+        Ok(())
+    }
+
+    /// Every cluster with its centroid, time span and members, then the noise
+    pub fn clusters(&self, clustering: &Clustering) {
+        if self.json {
+            let clusters: Vec<Value> = clustering
+                .clusters
+                .iter()
+                .map(|cluster| {
+                    json!({
+                        "centroid": { "lat": cluster.centroid.lat(), "lon": cluster.centroid.lon() },
+                        "id_span": cluster.id_span(),
+                        "members": cluster.members,
+                    })
+                })
+                .collect();
+            // No distances here, the unit is only kept for a uniform layout
+            self.emit(json!({ "clusters": clusters, "noise": clustering.noise }));
+            return;
+        }
+
+        let join = |ids: &[i32]| {
+            ids.iter()
+                .map(i32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        for (number, cluster) in clustering.clusters.iter().enumerate() {
+            println!(
+                "cluster {}: {} points around {:.4}°, {:.4}°, over {} id units",
+                number + 1,
+                cluster.members.len(),
+                cluster.centroid.lat(),
+                cluster.centroid.lon(),
+                cluster.id_span()
+            );
+            println!("  ids: {}", join(&cluster.members));
+        }
+        println!(
+            "noise: {} points{}",
+            clustering.noise.len(),
+            if clustering.noise.is_empty() {
+                String::new()
+            } else {
+                format!(" (ids: {})", join(&clustering.noise))
+            }
+        );
+    }
+
+    /// Spatial query results, one point per line
+    pub fn neighbours(&self, neighbours: &[Neighbour]) {
+        if self.json {
+            let neighbours: Vec<Value> = neighbours
+                .iter()
+                .map(|n| json!({ "id": n.id, "distance": self.number(n.distance) }))
+                .collect();
+            self.emit(json!({ "neighbours": neighbours }));
+            return;
+        }
+
+        for neighbour in neighbours {
+            println!("id {}: {}", neighbour.id, self.text(neighbour.distance));
+        }
+    }
+
+    /// Confirms that a file was written
+    pub fn written(&self, what: &str, path: &str, points: usize) {
+        if self.json {
+            self.emit(json!({ "output": path, "points": points }));
+        } else {
+            println!("{} written to {}", what, path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests unit conversion and rounding in both formats
+    #[test]
+    fn test_printer_formats() {
+        let printer = Printer {
+            unit: Unit::Mi,
+            precision: 1,
+            json: false,
+        };
+        let distance = Distance::from_km(16968.72);
+        assert_eq!(printer.text(distance), "10543.9 mi");
+        assert_eq!(printer.number(distance), json!(10543.9));
+
+        let meters = Printer {
+            unit: Unit::M,
+            precision: 0,
+            json: true,
+        };
+        assert_eq!(meters.text(Distance::from_km(1.2346)), "1235 m");
+        assert_eq!(meters.number(Distance::from_km(1.2346)), json!(1235.0));
+    }
+}
@@ -0,0 +1,221 @@
+use crossterm::style::{Color, Stylize};
+
+use crate::geo::{GeoPoint, TracePoint};
+
+/// Braille dot bits indexed by [row][column] inside a 2x4 character cell
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// First code point of the Unicode braille patterns block (no dot raised)
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// A character grid where every cell holds 2x4 braille dots
+struct BrailleCanvas {
+    columns: usize,
+    rows: usize,
+    cells: Vec<u8>,
+}
+
+impl BrailleCanvas {
+    fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            cells: vec![0; columns * rows],
+        }
+    }
+
+    /// Width of the canvas in dots
+    fn dot_width(&self) -> usize {
+        self.columns * 2
+    }
+
+    /// Height of the canvas in dots
+    fn dot_height(&self) -> usize {
+        self.rows * 4
+    }
+
+    /// Raises the dot at (x, y), dots outside the canvas are ignored
+    fn set(&mut self, x: i64, y: i64) {
+        if x < 0 || y < 0 || x as usize >= self.dot_width() || y as usize >= self.dot_height() {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self.cells[(y / 4) * self.columns + x / 2] |= BRAILLE_DOTS[y % 4][x % 2];
+    }
+
+    /// Draws a straight line of dots between two points (Bresenham's algorithm)
+    fn line(&mut self, (mut x0, mut y0): (i64, i64), (x1, y1): (i64, i64)) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.set(x0, y0);
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    /// Character cell containing the dot at (x, y)
+    fn cell_of(&self, (x, y): (i64, i64)) -> (usize, usize) {
+        (x as usize / 2, y as usize / 4)
+    }
+
+    /// Renders the grid, coloring the given cells
+    fn render(&self, highlights: &[((usize, usize), Color)], color: bool) -> String {
+        let mut out = String::new();
+
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let bits = self.cells[row * self.columns + column];
+                let c = char::from_u32(BRAILLE_BLANK + u32::from(bits)).unwrap_or(' ');
+
+                match highlights.iter().find(|(cell, _)| *cell == (column, row)) {
+                    Some(&(_, highlight)) if color => {
+                        out.push_str(&c.with(highlight).bold().to_string())
+                    }
+                    Some(_) => out.push('●'),
+                    None => out.push(c),
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Draws the sorted trace with braille characters in a `columns` x `rows` area
+///
+/// The points are converted to WGS84 and drawn in an equirectangular frame
+/// stretched to the area. The start (green) and end (red) cells are
+/// highlighted, with plain `●` markers when `color` is false, and the axis
+/// extents are printed in degrees around the plot.
+pub fn plot_trace(trace: &[TracePoint], columns: usize, rows: usize, color: bool) -> String {
+    // Convert points from Web Mercator to WGS84
+    let geo: Vec<GeoPoint> = trace.iter().map(|point| point.geo()).collect();
+
+    let (min_lon, max_lon, min_lat, max_lat) = geo.iter().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(min_lon, max_lon, min_lat, max_lat), point| {
+            (
+                min_lon.min(point.lon()),
+                max_lon.max(point.lon()),
+                min_lat.min(point.lat()),
+                max_lat.max(point.lat()),
+            )
+        },
+    );
+
+    let mut canvas = BrailleCanvas::new(columns.max(1), rows.max(1));
+    let (w, h) = (
+        canvas.dot_width() as f64 - 1.0,
+        canvas.dot_height() as f64 - 1.0,
+    );
+
+    // Map degrees to dots, north at the top; a zero extent collapses to the middle
+    let to_dot = |point: &GeoPoint| -> (i64, i64) {
+        let fx = if max_lon > min_lon {
+            (point.lon() - min_lon) / (max_lon - min_lon)
+        } else {
+            0.5
+        };
+        let fy = if max_lat > min_lat {
+            (max_lat - point.lat()) / (max_lat - min_lat)
+        } else {
+            0.5
+        };
+        ((fx * w).round() as i64, (fy * h).round() as i64)
+    };
+
+    let dots: Vec<(i64, i64)> = geo.iter().map(to_dot).collect();
+    for pair in dots.windows(2) {
+        canvas.line(pair[0], pair[1]);
+    }
+    if let [single] = dots.as_slice() {
+        canvas.set(single.0, single.1);
+    }
+
+    // The start comes first so it wins when both ends share a cell
+    let highlights: Vec<((usize, usize), Color)> = match (dots.first(), dots.last()) {
+        (Some(&first), Some(&last)) => vec![
+            (canvas.cell_of(first), Color::Green),
+            (canvas.cell_of(last), Color::Red),
+        ],
+        _ => Vec::new(),
+    };
+
+    let mut out = String::new();
+    if !geo.is_empty() {
+        out.push_str(&format!("lat {:.4}°\n", max_lat));
+    }
+    out.push_str(&canvas.render(&highlights, color));
+    if !geo.is_empty() {
+        out.push_str(&format!(
+            "lat {:.4}°   lon {:.4}° .. {:.4}°   ({} points, start green, end red)\n",
+            min_lat,
+            min_lon,
+            max_lon,
+            geo.len()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that dots map to the right braille bits and characters
+    #[test]
+    fn test_braille_canvas_set() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.set(0, 0); // top-left dot of the first cell
+        canvas.set(3, 3); // bottom-right dot of the second cell
+        canvas.set(10, 10); // out of bounds, ignored
+
+        assert_eq!(canvas.render(&[], false), "⠁⢀\n");
+    }
+
+    /// Tests that a horizontal line raises the top row of every dot
+    #[test]
+    fn test_braille_canvas_line() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.line((0, 0), (3, 0));
+
+        assert_eq!(canvas.render(&[], false), "⠉⠉\n");
+    }
+
+    /// Tests the full plot: size, extents and start/end markers
+    #[test]
+    fn test_plot_trace() {
+        let trace = [
+            TracePoint::new(1, 0.0, 0.0),
+            TracePoint::new(2, 1_000_000.0, 1_000_000.0),
+        ];
+        let out = plot_trace(&trace, 10, 4, false);
+        let lines: Vec<&str> = out.lines().collect();
+
+        // Header, 4 canvas rows, footer
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1].chars().count(), 10);
+        assert!(lines[0].starts_with("lat 8.9"));
+        assert!(lines[5].contains("lon 0.0000° .. 8.9832°"));
+
+        // Start is bottom-left and end top-right (north is up)
+        assert!(lines[4].starts_with('●'));
+        assert!(lines[1].ends_with('●'));
+    }
+}
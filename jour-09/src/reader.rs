@@ -0,0 +1,357 @@
+use eyre::{Context, Result};
+use std::io::{BufRead, Lines};
+use std::iter::Enumerate;
+
+use crate::geo::{DomainPolicy, MercatorPoint, TracePoint};
+
+/// Names of the columns holding the trace fields, in the default order
+const COLUMNS: [&str; 3] = ["id", "x", "y"];
+
+/// Name of the optional column identifying the sleigh in fleet logs
+const SLEIGH_COLUMN: &str = "sleigh_id";
+
+/// Options describing the layout of a trace file
+#[derive(Debug, Clone)]
+pub struct ReaderConfig {
+    /// Field separator
+    pub delimiter: char,
+    /// First data line holds column names, the fields are then looked up by name
+    pub has_header: bool,
+    /// Strip surrounding whitespace from every field
+    pub trim: bool,
+    /// Lines starting with this character are ignored
+    pub comment: Option<char>,
+    /// Rows start with a sleigh id: sleigh_id,id,x,y (with a header, the
+    /// `sleigh_id` column is picked up even without this flag)
+    pub fleet: bool,
+    /// What to do with coordinates outside the Web Mercator square
+    pub domain: DomainPolicy,
+}
+
+/// A parsed row: its 1-based line number, the sleigh it belongs to in fleet
+/// logs, and the point itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub line: usize,
+    pub sleigh: Option<String>,
+    pub point: TracePoint,
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_header: false,
+            trim: false,
+            comment: None,
+            fleet: false,
+            domain: DomainPolicy::Reject,
+        }
+    }
+}
+
+/// Position of the id, x, y and sleigh fields in a row, and the expected row width
+struct Layout {
+    indices: [usize; 3],
+    sleigh: Option<usize>,
+    width: usize,
+}
+
+impl Layout {
+    /// Positional layout used when the file has no header: id,x,y or sleigh_id,id,x,y
+    fn positional(fleet: bool) -> Self {
+        if fleet {
+            Self {
+                indices: [1, 2, 3],
+                sleigh: Some(0),
+                width: COLUMNS.len() + 1,
+            }
+        } else {
+            Self {
+                indices: [0, 1, 2],
+                sleigh: None,
+                width: COLUMNS.len(),
+            }
+        }
+    }
+
+    /// Builds the layout from a header row, matching names case-insensitively
+    fn from_header(fields: &[&str], line_number: usize, fleet: bool) -> Result<Self> {
+        let mut indices = [0; 3];
+        let sleigh = fields
+            .iter()
+            .position(|field| field.eq_ignore_ascii_case(SLEIGH_COLUMN));
+
+        if fleet && sleigh.is_none() {
+            eyre::bail!(
+                "line {}: header is missing column '{}'",
+                line_number,
+                SLEIGH_COLUMN
+            );
+        }
+
+        for (slot, name) in indices.iter_mut().zip(COLUMNS) {
+            *slot = fields
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    eyre::eyre!("line {}: header is missing column '{}'", line_number, name)
+                })?;
+        }
+
+        Ok(Self {
+            indices,
+            sleigh,
+            width: fields.len(),
+        })
+    }
+}
+
+/// Streaming reader of trace records, one record per data line
+///
+/// Yields records in file order with 1-based line numbers, without keeping
+/// anything but the current line in memory.
+pub struct Records<'a, R> {
+    lines: Enumerate<Lines<R>>,
+    config: &'a ReaderConfig,
+    layout: Option<Layout>,
+}
+
+/// Streams the records of any buffered source according to `config`
+pub fn records<R: BufRead>(reader: R, config: &ReaderConfig) -> Records<'_, R> {
+    Records {
+        lines: reader.lines().enumerate(),
+        config,
+        layout: (!config.has_header).then(|| Layout::positional(config.fleet)),
+    }
+}
+
+/// Reads trace records from any buffered source according to `config`
+///
+/// Output: records in file order, with 1-based line numbers
+///
+/// # Errors
+/// Every error names the line, and when a field is at fault the column name
+/// and the raw value
+pub fn read_records<R: BufRead>(reader: R, config: &ReaderConfig) -> Result<Vec<Record>> {
+    records(reader, config).collect()
+}
+
+impl<R: BufRead> Records<'_, R> {
+    /// Parses one line: None for blank, comment and header lines
+    fn parse_line(&mut self, line_number: usize, line: &str) -> Result<Option<Record>> {
+        let config = self.config;
+
+        // Blank lines (such as a trailing newline) and comments carry no data
+        let content = line.trim();
+        if content.is_empty() || config.comment.is_some_and(|c| content.starts_with(c)) {
+            return Ok(None);
+        }
+
+        let fields: Vec<&str> = line
+            .split(config.delimiter)
+            .map(|field| if config.trim { field.trim() } else { field })
+            .collect();
+
+        // The first data line is the header when one is expected
+        let Some(layout) = self.layout.as_ref() else {
+            self.layout = Some(Layout::from_header(&fields, line_number, config.fleet)?);
+            return Ok(None);
+        };
+
+        if fields.len() != layout.width {
+            eyre::bail!(
+                "line {}: expected {} columns, found {} in '{}'",
+                line_number,
+                layout.width,
+                fields.len(),
+                line
+            );
+        }
+
+        let [id, x, y] = layout.indices.map(|i| fields[i]);
+
+        let id = parse_field(id, "id", line_number)?;
+        let position = MercatorPoint::validated(
+            parse_field(x, "x", line_number)?,
+            parse_field(y, "y", line_number)?,
+            config.domain,
+        )
+        .map_err(|err| eyre::eyre!("line {}: {}", line_number, err))?;
+
+        Ok(Some(Record {
+            line: line_number,
+            sleigh: layout.sleigh.map(|i| fields[i].to_string()),
+            point: TracePoint { id, position },
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for Records<'_, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, line)) = self.lines.next() {
+            let line_number = index + 1;
+            let parsed = line
+                .wrap_err_with(|| format!("line {}: failed to read line", line_number))
+                .and_then(|line| self.parse_line(line_number, &line));
+            // Blank, comment and header lines yield nothing, keep reading
+            if let Some(item) = parsed.transpose() {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+/// Parses a single field, naming the line, the column and the raw value on failure
+fn parse_field<T>(raw: &str, column: &str, line_number: usize) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    raw.parse().map_err(|err| {
+        eyre::eyre!(
+            "line {}, column '{}': invalid value '{}' ({})",
+            line_number,
+            column,
+            raw,
+            err
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the default positional layout
+    #[test]
+    fn test_read_records_default() -> Result<()> {
+        let records = read_records("2,10.5,20.5\n1,0,0\n".as_bytes(), &ReaderConfig::default())?;
+
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    line: 1,
+                    sleigh: None,
+                    point: TracePoint::new(2, 10.5, 20.5)
+                },
+                Record {
+                    line: 2,
+                    sleigh: None,
+                    point: TracePoint::new(1, 0.0, 0.0)
+                }
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// Tests a header with reordered and extra named columns
+    ///
+    /// The `speed` column is named in the header so it is allowed, and the
+    /// fields are looked up by name regardless of their position.
+    #[test]
+    fn test_read_records_header() -> Result<()> {
+        let config = ReaderConfig {
+            has_header: true,
+            ..Default::default()
+        };
+        let input = "y,speed,ID,x\n20.5,3,7,10.5\n";
+        let records = read_records(input.as_bytes(), &config)?;
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].line, 2);
+        assert_eq!(records[0].point, TracePoint::new(7, 10.5, 20.5));
+
+        Ok(())
+    }
+
+    /// Tests delimiter, trimming and comment options together
+    #[test]
+    fn test_read_records_options() -> Result<()> {
+        let config = ReaderConfig {
+            delimiter: ';',
+            trim: true,
+            comment: Some('#'),
+            ..Default::default()
+        };
+        let input = "# sleigh log\n 1 ; 10.0 ;20.0\n\n  # end\n";
+        let records = read_records(input.as_bytes(), &config)?;
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].line, 2);
+        assert_eq!(records[0].point, TracePoint::new(1, 10.0, 20.0));
+
+        Ok(())
+    }
+
+    /// Tests the sleigh column, positional and from a header
+    #[test]
+    fn test_read_records_fleet() -> Result<()> {
+        let config = ReaderConfig {
+            fleet: true,
+            ..Default::default()
+        };
+        let records = read_records("rudolph,1,10,20\ncomet,1,0,0\n".as_bytes(), &config)?;
+        assert_eq!(records[0].sleigh.as_deref(), Some("rudolph"));
+        assert_eq!(records[1].sleigh.as_deref(), Some("comet"));
+        assert_eq!(records[1].point, TracePoint::new(1, 0.0, 0.0));
+
+        // A sleigh_id column in a header is used without --fleet
+        let header = ReaderConfig {
+            has_header: true,
+            ..Default::default()
+        };
+        let records = read_records("id,x,y,Sleigh_Id\n3,1,2,dasher\n".as_bytes(), &header)?;
+        assert_eq!(records[0].sleigh.as_deref(), Some("dasher"));
+        assert_eq!(records[0].point, TracePoint::new(3, 1.0, 2.0));
+
+        let fleet_header = ReaderConfig {
+            has_header: true,
+            fleet: true,
+            ..Default::default()
+        };
+        let err = read_records("id,x,y\n".as_bytes(), &fleet_header).unwrap_err();
+        assert!(err.to_string().contains("missing column 'sleigh_id'"));
+
+        Ok(())
+    }
+
+    /// Tests that errors name the line, the column and the raw value
+    #[test]
+    fn test_read_records_errors() {
+        let config = ReaderConfig::default();
+
+        let err = read_records("1,0,0\n2,abc,0\n".as_bytes(), &config).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("line 2, column 'x': invalid value 'abc'")
+        );
+
+        let err = read_records("1,NaN,0\n".as_bytes(), &config).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: x = NaN is not a finite number");
+
+        let err = read_records("1,0,2.1e7\n".as_bytes(), &config).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("line 1: y = 21000000 m is beyond the Web Mercator limit")
+        );
+
+        let err = read_records("1,0,0,9\n".as_bytes(), &config).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("line 1: expected 3 columns, found 4")
+        );
+
+        let header = ReaderConfig {
+            has_header: true,
+            ..Default::default()
+        };
+        let err = read_records("id,x\n".as_bytes(), &header).unwrap_err();
+        assert!(err.to_string().contains("missing column 'y'"));
+    }
+}
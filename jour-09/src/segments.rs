@@ -0,0 +1,197 @@
+use std::fmt::Write;
+
+use crate::geo::{Distance, TracePoint, Unit};
+
+/// A leg between two consecutive points of the sorted trace
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from: i32,
+    pub to: i32,
+    /// Bearing when leaving `from`, in degrees clockwise from north
+    pub initial_bearing: f64,
+    /// Bearing when arriving at `to`, in degrees clockwise from north
+    pub final_bearing: f64,
+    pub length: Distance,
+    /// Turn at `from` coming from the previous segment, in degrees in
+    /// (-180°, 180°], positive to the right; None for the first segment and
+    /// around zero-length segments, whose heading is undefined
+    pub turn: Option<f64>,
+}
+
+/// The segments of a trace with their turning statistics
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentReport {
+    pub segments: Vec<Segment>,
+}
+
+/// Brings an angle difference into (-180°, 180°]
+fn normalize_turn(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 180.0 { angle - 360.0 } else { angle }
+}
+
+/// Computes the bearings, length and turn of every segment of the sorted trace
+pub fn segment_report(trace: &[TracePoint]) -> SegmentReport {
+    let mut segments: Vec<Segment> = Vec::with_capacity(trace.len().saturating_sub(1));
+
+    for pair in trace.windows(2) {
+        let (a, b) = (pair[0].geo(), pair[1].geo());
+        let length = a.great_circle_distance(b);
+        let initial_bearing = a.initial_bearing(b);
+
+        let turn = match segments.last() {
+            Some(previous) if previous.length.km() > 0.0 && length.km() > 0.0 => {
+                Some(normalize_turn(initial_bearing - previous.final_bearing))
+            }
+            _ => None,
+        };
+
+        segments.push(Segment {
+            from: pair[0].id,
+            to: pair[1].id,
+            initial_bearing,
+            final_bearing: a.final_bearing(b),
+            length,
+            turn,
+        });
+    }
+
+    SegmentReport { segments }
+}
+
+impl SegmentReport {
+    /// Sum of the absolute turn angles, in degrees
+    pub fn total_turning(&self) -> f64 {
+        self.segments
+            .iter()
+            .filter_map(|s| s.turn)
+            .map(f64::abs)
+            .sum()
+    }
+
+    /// Sum of the signed turn angles, in degrees: positive when the trace
+    /// turned right overall
+    pub fn net_turning(&self) -> f64 {
+        self.segments.iter().filter_map(|s| s.turn).sum()
+    }
+
+    /// The `count` sharpest turns as (vertex id, signed angle), sharpest first
+    pub fn sharpest_turns(&self, count: usize) -> Vec<(i32, f64)> {
+        let mut turns: Vec<(i32, f64)> = self
+            .segments
+            .iter()
+            .filter_map(|s| s.turn.map(|turn| (s.from, turn)))
+            .collect();
+        turns.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        turns.truncate(count);
+        turns
+    }
+
+    /// Aligned table of the segments, lengths in `unit` with `precision` decimals
+    pub fn to_table(&self, unit: Unit, precision: usize) -> String {
+        let mut out = format!(
+            "{:>6} {:>6} {:>9} {:>9} {:>8} {:>11}\n",
+            "from",
+            "to",
+            "initial°",
+            "final°",
+            "turn°",
+            format!("length {}", unit.symbol())
+        );
+        for s in &self.segments {
+            let turn = s.turn.map(|t| format!("{:.1}", t)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{:>6} {:>6} {:>9.1} {:>9.1} {:>8} {:>11.*}",
+                s.from,
+                s.to,
+                s.initial_bearing,
+                s.final_bearing,
+                turn,
+                precision,
+                s.length.in_unit(unit)
+            );
+        }
+        out
+    }
+
+    /// CSV of the segments, with a header row, for plotting
+    pub fn to_csv(&self, unit: Unit) -> String {
+        let mut out = format!(
+            "from_id,to_id,initial_bearing,final_bearing,turn,length_{}\n",
+            unit.symbol()
+        );
+        for s in &self.segments {
+            let turn = s.turn.map(|t| format!("{:.4}", t)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{},{},{:.4},{:.4},{},{:.4}",
+                s.from,
+                s.to,
+                s.initial_bearing,
+                s.final_bearing,
+                turn,
+                s.length.in_unit(unit)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::trace;
+
+    /// Tests turn angles on a small loop near the equator
+    ///
+    /// North, then east (right turn of ~90°), then south (right turn of
+    /// ~90°), then back west along the equator.
+    #[test]
+    fn test_segment_report_turns() -> eyre::Result<()> {
+        let report = segment_report(&trace(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)])?);
+
+        assert_eq!(report.segments.len(), 3);
+        assert!(report.segments[0].initial_bearing.abs() < 1e-9);
+        assert!(report.segments[0].turn.is_none());
+        assert!((report.segments[1].turn.unwrap() - 90.0).abs() < 0.1);
+        assert!((report.segments[2].turn.unwrap() - 90.0).abs() < 0.1);
+
+        assert!((report.total_turning() - 180.0).abs() < 0.2);
+        assert_eq!(report.sharpest_turns(1).len(), 1);
+
+        Ok(())
+    }
+
+    /// Tests left turns, the sign convention and zero-length segments
+    #[test]
+    fn test_segment_report_left_and_degenerate() -> eyre::Result<()> {
+        // East along the equator, then north: a left turn
+        let report = segment_report(&trace(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)])?);
+        assert!((report.segments[1].turn.unwrap() + 90.0).abs() < 0.1);
+        assert!((report.net_turning() + 90.0).abs() < 0.1);
+
+        // Standing still has no heading, so no turn is computed around it
+        let report = segment_report(&trace(&[(0.0, 0.0), (0.0, 1.0), (0.0, 1.0), (1.0, 1.0)])?);
+        assert!(report.segments.iter().all(|s| s.turn.is_none()));
+
+        assert_eq!(normalize_turn(270.0), -90.0);
+        assert_eq!(normalize_turn(-180.0), 180.0);
+
+        Ok(())
+    }
+
+    /// Tests the CSV layout
+    #[test]
+    fn test_segment_report_csv() -> eyre::Result<()> {
+        let csv = segment_report(&trace(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)])?).to_csv(Unit::Km);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(",turn,length_km"));
+        assert!(lines[1].starts_with("1,2,90.0000,90.0000,,111.19"));
+        assert!(lines[2].starts_with("2,3,0.0000,0.0000,-90.0000,111.19"));
+
+        Ok(())
+    }
+}
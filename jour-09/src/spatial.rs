@@ -0,0 +1,289 @@
+use crate::geo::{Distance, EARTH_MEAN_RADIUS_KM, GeoPoint, TracePoint};
+
+/// A trace point with its position on the unit sphere
+#[derive(Debug, Clone)]
+struct IndexedPoint {
+    id: i32,
+    geo: GeoPoint,
+    xyz: [f64; 3],
+}
+
+/// A query result: the point id and its great-circle distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbour {
+    pub id: i32,
+    pub distance: Distance,
+}
+
+/// k-d tree over the trace points, stored as 3D unit vectors
+///
+/// The straight-line (chord) distance between two unit vectors grows with the
+/// great-circle distance, so the Euclidean k-d tree finds exactly the same
+/// neighbours as a scan of great-circle distances, without any trouble at the
+/// poles or the antimeridian. Reported distances are then the great-circle ones.
+///
+/// The tree is implicit: every slice stores its median at the middle, with
+/// the smaller half on the left and the larger half on the right.
+pub struct SpatialIndex {
+    points: Vec<IndexedPoint>,
+}
+
+/// Converts a position to a 3D unit vector
+fn to_unit_vector(point: GeoPoint) -> [f64; 3] {
+    let (lat, lon) = (point.lat().to_radians(), point.lon().to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Squared Euclidean distance between two 3D vectors
+fn chord_squared(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+/// Chord length on the unit sphere matching a great-circle distance
+fn chord_for(distance: Distance) -> f64 {
+    let angle = (distance.km() / EARTH_MEAN_RADIUS_KM).min(std::f64::consts::PI);
+    2.0 * (angle / 2.0).sin()
+}
+
+impl SpatialIndex {
+    /// Builds the index from the trace points
+    pub fn build(trace: &[TracePoint]) -> Self {
+        Self::from_positions(trace.iter().map(|point| (point.id, point.geo())))
+    }
+
+    /// Builds the index from arbitrary (id, position) pairs
+    pub fn from_positions(positions: impl IntoIterator<Item = (i32, GeoPoint)>) -> Self {
+        let mut points: Vec<IndexedPoint> = positions
+            .into_iter()
+            .map(|(id, geo)| IndexedPoint {
+                id,
+                geo,
+                xyz: to_unit_vector(geo),
+            })
+            .collect();
+
+        build_tree(&mut points, 0);
+
+        Self { points }
+    }
+
+    /// Returns the `k` points closest to `origin`, nearest first
+    pub fn nearest(&self, origin: GeoPoint, k: usize) -> Vec<Neighbour> {
+        let target = to_unit_vector(origin);
+        // Best candidates so far as (squared chord, index), sorted ascending
+        let mut best: Vec<(f64, usize)> = Vec::with_capacity(k + 1);
+
+        if k > 0 {
+            self.search_nearest(0, self.points.len(), 0, &target, k, &mut best);
+        }
+
+        best.into_iter()
+            .map(|(_, index)| self.neighbour(index, origin))
+            .collect()
+    }
+
+    /// Returns every point within `radius` of `origin`, nearest first
+    pub fn within_radius(&self, origin: GeoPoint, radius: Distance) -> Vec<Neighbour> {
+        let target = to_unit_vector(origin);
+        let limit = chord_for(radius).powi(2);
+        let mut found = Vec::new();
+
+        self.search_radius(0, self.points.len(), 0, &target, limit, &mut found);
+
+        let mut neighbours: Vec<Neighbour> = found
+            .into_iter()
+            .map(|index| self.neighbour(index, origin))
+            // The chord test is exact in theory; the great-circle distance has the final word
+            .filter(|n| n.distance <= radius)
+            .collect();
+        neighbours.sort_by(|a, b| a.distance.km().total_cmp(&b.distance.km()));
+        neighbours
+    }
+
+    fn neighbour(&self, index: usize, origin: GeoPoint) -> Neighbour {
+        let point = &self.points[index];
+        Neighbour {
+            id: point.id,
+            distance: origin.great_circle_distance(point.geo),
+        }
+    }
+
+    /// Visits the subtree stored in points[start..end]
+    fn search_nearest(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &[f64; 3],
+        k: usize,
+        best: &mut Vec<(f64, usize)>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let axis = depth % 3;
+
+        // Insert the median keeping the candidates sorted and at most k long
+        let d = chord_squared(&self.points[mid].xyz, target);
+        if best.len() < k || d < best[best.len() - 1].0 {
+            let position = best.partition_point(|&(other, _)| other <= d);
+            best.insert(position, (d, mid));
+            best.truncate(k);
+        }
+
+        // Search the side of the splitting plane containing the target first
+        let diff = target[axis] - self.points[mid].xyz[axis];
+        let (near, far) = if diff < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+
+        self.search_nearest(near.0, near.1, depth + 1, target, k, best);
+
+        // The far side can only help if the plane is closer than the worst candidate
+        if best.len() < k || diff * diff < best[best.len() - 1].0 {
+            self.search_nearest(far.0, far.1, depth + 1, target, k, best);
+        }
+    }
+
+    /// Collects the indices of the points of points[start..end] within `limit`
+    /// (squared chord)
+    fn search_radius(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &[f64; 3],
+        limit: f64,
+        found: &mut Vec<usize>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let axis = depth % 3;
+
+        if chord_squared(&self.points[mid].xyz, target) <= limit {
+            found.push(mid);
+        }
+
+        let diff = target[axis] - self.points[mid].xyz[axis];
+        if diff < 0.0 || diff * diff <= limit {
+            self.search_radius(start, mid, depth + 1, target, limit, found);
+        }
+        if diff >= 0.0 || diff * diff <= limit {
+            self.search_radius(mid + 1, end, depth + 1, target, limit, found);
+        }
+    }
+}
+
+/// Arranges the points as an implicit k-d tree, splitting on x, y, z in turn
+fn build_tree(points: &mut [IndexedPoint], depth: usize) {
+    if points.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let mid = points.len() / 2;
+
+    points.select_nth_unstable_by(mid, |a, b| a.xyz[axis].total_cmp(&b.xyz[axis]));
+
+    let (left, right) = points.split_at_mut(mid);
+    build_tree(left, depth + 1);
+    build_tree(&mut right[1..], depth + 1);
+}
+
+/// Every trace point with its distance to `origin`, nearest first
+///
+/// The O(n log n) reference the index is checked and benchmarked against.
+pub fn linear_scan(trace: &[TracePoint], origin: GeoPoint) -> Vec<Neighbour> {
+    let mut neighbours: Vec<Neighbour> = trace
+        .iter()
+        .map(|point| Neighbour {
+            id: point.id,
+            distance: origin.great_circle_distance(point.geo()),
+        })
+        .collect();
+    neighbours.sort_by(|a, b| a.distance.km().total_cmp(&b.distance.km()));
+    neighbours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random trace spread over the whole Mercator plane
+    fn trace(len: i32) -> Vec<TracePoint> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            // xorshift64, mapped to [-1, 1)
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+        };
+        (1..=len)
+            .map(|id| TracePoint::new(id, next() * 20_037_508.0, next() * 15_000_000.0))
+            .collect()
+    }
+
+    /// Tests that nearest matches a linear scan, around the globe and at the poles
+    #[test]
+    fn test_nearest_matches_linear_scan() -> eyre::Result<()> {
+        let trace = trace(500);
+        let index = SpatialIndex::build(&trace);
+
+        for &(lat, lon) in &[(45.76, 4.84), (-33.9, 151.2), (89.9, 179.9), (0.0, -180.0)] {
+            let origin = GeoPoint::new(lat, lon)?;
+            let expected: Vec<i32> = linear_scan(&trace, origin)
+                .iter()
+                .take(5)
+                .map(|n| n.id)
+                .collect();
+            let found: Vec<i32> = index.nearest(origin, 5).iter().map(|n| n.id).collect();
+            assert_eq!(found, expected);
+        }
+
+        Ok(())
+    }
+
+    /// Tests that within_radius matches a linear scan
+    #[test]
+    fn test_within_radius_matches_linear_scan() -> eyre::Result<()> {
+        let trace = trace(500);
+        let index = SpatialIndex::build(&trace);
+        let lyon = GeoPoint::new(45.76, 4.84)?;
+
+        for radius in [0.0, 500.0, 2000.0, 30_000.0].map(Distance::from_km) {
+            let expected: Vec<i32> = linear_scan(&trace, lyon)
+                .iter()
+                .filter(|n| n.distance <= radius)
+                .map(|n| n.id)
+                .collect();
+            let found: Vec<i32> = index
+                .within_radius(lyon, radius)
+                .iter()
+                .map(|n| n.id)
+                .collect();
+            assert_eq!(found, expected);
+        }
+
+        Ok(())
+    }
+
+    /// Tests queries on an empty index
+    #[test]
+    fn test_empty_index() -> eyre::Result<()> {
+        let index = SpatialIndex::build(&[]);
+        let origin = GeoPoint::new(0.0, 0.0)?;
+        assert!(index.nearest(origin, 3).is_empty());
+        assert!(
+            index
+                .within_radius(origin, Distance::from_km(100.0))
+                .is_empty()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,521 @@
+use clap::ValueEnum;
+use eyre::{Context, Result};
+use std::fmt::Write;
+use std::fs;
+
+use crate::geo::{GeoPoint, MERCATOR_LIMIT_M, MercatorPoint, TracePoint};
+
+/// Meters per degree along the equator of the Web Mercator projection, used
+/// to give the equirectangular projection the same scale
+const METERS_PER_DEGREE: f64 = MERCATOR_LIMIT_M / 180.0;
+
+/// Empty space kept around the drawing, in pixels
+pub const MARGIN: f64 = 40.0;
+
+/// Map projection used to draw the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Projection {
+    /// Web Mercator, as in the input file (conformal, distorts high latitudes)
+    #[default]
+    Mercator,
+    /// Plate carrée: longitude and latitude used directly as x and y
+    Equirectangular,
+}
+
+impl Projection {
+    /// Projects a position to plane coordinates in meters where y grows northwards
+    fn project(self, point: GeoPoint) -> (f64, f64) {
+        match self {
+            Projection::Mercator => {
+                let MercatorPoint { x, y } = point.to_mercator();
+                (x, y)
+            }
+            Projection::Equirectangular => (
+                point.lon() * METERS_PER_DEGREE,
+                point.lat() * METERS_PER_DEGREE,
+            ),
+        }
+    }
+
+    /// Converts plane coordinates back to (lat, lon) degrees
+    ///
+    /// Points beyond the poles of the equirectangular projection give
+    /// latitudes outside [-90°, 90°], hence raw degrees and not a `GeoPoint`.
+    fn unproject(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Projection::Mercator => {
+                let point = MercatorPoint::new(x, y).to_wgs84();
+                (point.lat(), point.lon())
+            }
+            Projection::Equirectangular => (y / METERS_PER_DEGREE, x / METERS_PER_DEGREE),
+        }
+    }
+}
+
+/// Drawing options for the SVG renderer
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    pub projection: Projection,
+    /// Width of the image in pixels, the height follows the trace aspect ratio
+    pub width: f64,
+    /// Write the id next to every point
+    pub labels: bool,
+    /// Draw meridians and parallels
+    pub graticule: bool,
+    /// Draw a distance scale bar
+    pub scale_bar: bool,
+    /// Polylines drawn under the trace
+    pub coastline: Vec<Vec<GeoPoint>>,
+}
+
+/// Maps plane coordinates to pixels, fitting the trace into the image
+struct Viewport {
+    projection: Projection,
+    min_x: f64,
+    max_y: f64,
+    scale: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Viewport {
+    /// Fits the bounding box of the projected points into `width` pixels
+    fn fit(projection: Projection, points: &[GeoPoint], width: f64) -> Self {
+        let projected: Vec<(f64, f64)> = points
+            .iter()
+            .map(|&point| projection.project(point))
+            .collect();
+
+        let (min_x, max_x, min_y, max_y) = projected.iter().fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+
+        // A single point (or a perfectly straight line) still needs a non-zero
+        // extent, one meter
+        let span_x = (max_x - min_x).max(1.0);
+        let span_y = (max_y - min_y).max(1.0);
+
+        let scale = (width - 2.0 * MARGIN) / span_x.max(span_y);
+        let height = span_y * scale + 2.0 * MARGIN;
+
+        Self {
+            projection,
+            min_x,
+            max_y,
+            scale,
+            width,
+            height,
+        }
+    }
+
+    /// Converts a position to pixel coordinates (y grows downwards)
+    fn to_pixel(&self, point: GeoPoint) -> (f64, f64) {
+        let (x, y) = self.projection.project(point);
+        (
+            MARGIN + (x - self.min_x) * self.scale,
+            MARGIN + (self.max_y - y) * self.scale,
+        )
+    }
+
+    /// Converts pixel coordinates back to (lat, lon) degrees, see `unproject`
+    fn to_lat_lon(&self, px: f64, py: f64) -> (f64, f64) {
+        let x = (px - MARGIN) / self.scale + self.min_x;
+        let y = self.max_y - (py - MARGIN) / self.scale;
+        self.projection.unproject(x, y)
+    }
+}
+
+/// Renders the sorted trace as a standalone SVG document
+///
+/// Input: trace points sorted by id
+/// Output: the SVG markup, no external resource (tiles, fonts) is referenced
+///
+/// # Errors
+/// Returns an error for an empty trace, which has no extent to fit, or when
+/// the width leaves no room inside the margins
+pub fn render_svg(trace: &[TracePoint], options: &SvgOptions) -> Result<String> {
+    if trace.is_empty() {
+        eyre::bail!("The trace is empty, there is nothing to draw");
+    }
+    if !(options.width > 2.0 * MARGIN && options.width.is_finite()) {
+        eyre::bail!(
+            "The image must be more than {} pixels wide (twice the margin), got {}",
+            2.0 * MARGIN,
+            options.width
+        );
+    }
+
+    // Convert points from Web Mercator to WGS84
+    let geo: Vec<GeoPoint> = trace.iter().map(|point| point.geo()).collect();
+
+    let view = Viewport::fit(options.projection, &geo, options.width);
+    let mut svg = String::new();
+
+    // Writing into a String cannot fail, hence the ignored results below
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.0} {h:.0}" font-family="sans-serif" font-size="10">"#,
+        w = view.width,
+        h = view.height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#f8fbff"/>"##
+    );
+
+    if options.graticule {
+        draw_graticule(&mut svg, &view);
+    }
+
+    for line in &options.coastline {
+        let _ = writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#8aa" stroke-width="0.8"/>"##,
+            polyline_points(&view, line)
+        );
+    }
+
+    let _ = writeln!(
+        svg,
+        r##"<polyline points="{}" fill="none" stroke="#c0392b" stroke-width="1.5" stroke-linejoin="round"/>"##,
+        polyline_points(&view, &geo)
+    );
+
+    if options.labels {
+        for (point, &position) in trace.iter().zip(&geo) {
+            let (px, py) = view.to_pixel(position);
+            let _ = writeln!(
+                svg,
+                r##"<text x="{:.1}" y="{:.1}" fill="#333">{}</text>"##,
+                px + 3.0,
+                py - 3.0,
+                point.id
+            );
+        }
+    }
+
+    // Start and end markers are drawn last so they stay on top
+    if let (Some(&first), Some(&last)) = (geo.first(), geo.last()) {
+        draw_marker(&mut svg, &view, first, "#27ae60", "start");
+        draw_marker(&mut svg, &view, last, "#2c3e50", "end");
+    }
+
+    if options.scale_bar {
+        draw_scale_bar(&mut svg, &view);
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Formats geographic points as the `points` attribute of a polyline
+fn polyline_points(view: &Viewport, points: &[GeoPoint]) -> String {
+    points
+        .iter()
+        .map(|&point| {
+            let (px, py) = view.to_pixel(point);
+            format!("{:.1},{:.1}", px, py)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Draws a labelled circle at a geographic point
+fn draw_marker(svg: &mut String, view: &Viewport, point: GeoPoint, color: &str, label: &str) {
+    let (px, py) = view.to_pixel(point);
+    let _ = writeln!(
+        svg,
+        r#"<circle cx="{px:.1}" cy="{py:.1}" r="5" fill="{color}" stroke="white" stroke-width="1.5"/>"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" fill="{color}" font-weight="bold">{label}</text>"#,
+        px + 7.0,
+        py + 4.0
+    );
+}
+
+/// Draws meridians and parallels every few degrees over the visible area
+///
+/// Both supported projections are cylindrical, so meridians are vertical
+/// and parallels horizontal straight lines.
+fn draw_graticule(svg: &mut String, view: &Viewport) {
+    let (north, west) = view.to_lat_lon(0.0, 0.0);
+    let (south, east) = view.to_lat_lon(view.width, view.height);
+    let step = graticule_step((east - west).max(north - south));
+
+    for meridian in multiples(west.max(-180.0), east.min(180.0), step)
+        .filter_map(|lon| GeoPoint::new(0.0, lon).ok())
+    {
+        let (px, _) = view.to_pixel(meridian);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{px:.1}" y1="0" x2="{px:.1}" y2="{:.0}" stroke="#ccd" stroke-width="0.5"/>"##,
+            view.height
+        );
+        let _ = writeln!(
+            svg,
+            r##"<text x="{:.1}" y="{:.0}" fill="#889">{}°</text>"##,
+            px + 2.0,
+            view.height - 4.0,
+            meridian.lon()
+        );
+    }
+
+    // Parallels beyond the poles (equirectangular margins) are rejected by GeoPoint
+    for parallel in multiples(south.max(-90.0), north.min(90.0), step)
+        .filter_map(|lat| GeoPoint::new(lat, 0.0).ok())
+    {
+        let (_, py) = view.to_pixel(parallel);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="0" y1="{py:.1}" x2="{:.0}" y2="{py:.1}" stroke="#ccd" stroke-width="0.5"/>"##,
+            view.width
+        );
+        let _ = writeln!(
+            svg,
+            r##"<text x="2" y="{:.1}" fill="#889">{}°</text>"##,
+            py - 2.0,
+            parallel.lat()
+        );
+    }
+}
+
+/// Multiples of `step` between two bounds, none if they are not finite
+///
+/// Adding 0.0 turns a -0.0 start into 0.0 so the label does not read "-0°".
+fn multiples(from: f64, to: f64, step: f64) -> impl Iterator<Item = f64> {
+    let valid = from.is_finite() && to.is_finite() && step.is_finite() && step > 0.0;
+    let start = valid.then(|| (from / step).ceil() * step + 0.0);
+    std::iter::successors(start, move |v| Some(v + step)).take_while(move |&v| v <= to)
+}
+
+/// Picks a round graticule spacing giving a handful of lines over `extent` degrees
+fn graticule_step(extent: f64) -> f64 {
+    [1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 45.0]
+        .into_iter()
+        .find(|step| extent / step <= 10.0)
+        .unwrap_or(90.0)
+}
+
+/// Draws a scale bar in the bottom-left corner, measured along the parallel
+/// it is drawn on (the Mercator scale varies with latitude)
+fn draw_scale_bar(svg: &mut String, view: &Viewport) {
+    let x0 = MARGIN;
+    let y = view.height - MARGIN / 2.0;
+
+    // Ground distance covered by 100 pixels at the bar's latitude
+    let (lat, lon1) = view.to_lat_lon(x0, y);
+    let (_, lon2) = view.to_lat_lon(x0 + 100.0, y);
+    let (Ok(left), Ok(right)) = (GeoPoint::new(lat, lon1), GeoPoint::new(lat, lon2)) else {
+        // The bottom of the image lies beyond a pole
+        return;
+    };
+    let km_per_100px = left.great_circle_distance(right).km();
+    if km_per_100px <= 0.0 {
+        return;
+    }
+
+    let km = nice_distance(km_per_100px);
+    let length = 100.0 * km / km_per_100px;
+
+    let _ = writeln!(
+        svg,
+        r##"<path d="M{x0:.1},{:.1} V{y:.1} H{:.1} V{:.1}" fill="none" stroke="#333" stroke-width="1.5"/>"##,
+        y - 4.0,
+        x0 + length,
+        y - 4.0
+    );
+    let _ = writeln!(
+        svg,
+        r##"<text x="{:.1}" y="{:.1}" fill="#333">{} km</text>"##,
+        x0 + length + 5.0,
+        y,
+        km
+    );
+}
+
+/// Rounds a distance down to 1, 2 or 5 times a power of ten
+fn nice_distance(max_km: f64) -> f64 {
+    let magnitude = 10f64.powf(max_km.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&d| d <= max_km)
+        .unwrap_or(magnitude)
+}
+
+/// Loads a coastline file: one "lon,lat" pair in degrees per line, polylines
+/// separated by blank lines, `#` comments allowed
+pub fn load_coastline(file_path: &str) -> Result<Vec<Vec<GeoPoint>>> {
+    let content = fs::read_to_string(file_path)
+        .wrap_err_with(|| format!("Failed to read coastline '{}'", file_path))?;
+
+    let mut lines = Vec::new();
+    let mut current = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        let (lon, lat) = line.split_once(',').ok_or_else(|| {
+            eyre::eyre!("line {}: expected 'lon,lat', found '{}'", index + 1, line)
+        })?;
+        let parse = |raw: &str| {
+            raw.trim()
+                .parse::<f64>()
+                .wrap_err_with(|| format!("line {}: invalid coordinate '{}'", index + 1, raw))
+        };
+        let point = GeoPoint::new(parse(lat)?, parse(lon)?)
+            .wrap_err_with(|| format!("line {}: invalid position", index + 1))?;
+        current.push(point);
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as _;
+    use tempfile::NamedTempFile;
+
+    fn options() -> SvgOptions {
+        SvgOptions {
+            projection: Projection::Mercator,
+            width: 400.0,
+            labels: true,
+            graticule: true,
+            scale_bar: true,
+            coastline: Vec::new(),
+        }
+    }
+
+    /// Tests that the document contains the trace, markers, labels and decorations
+    #[test]
+    fn test_render_svg() {
+        let trace = [
+            TracePoint::new(1, 0.0, 0.0),
+            TracePoint::new(2, 500_000.0, 300_000.0),
+            TracePoint::new(3, 1_000_000.0, 0.0),
+        ];
+        let svg = render_svg(&trace, &options()).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("<polyline"));
+        assert!(svg.contains(">start</text>"));
+        assert!(svg.contains(">end</text>"));
+        assert!(svg.contains(">2</text>")); // id label
+        assert!(svg.contains(" km</text>")); // scale bar
+        assert!(svg.contains("°</text>")); // graticule
+        assert!(!svg.contains("NaN"));
+    }
+
+    /// Tests that the start marker is at the left margin for an eastward trace
+    /// and that projections fit the trace into the requested width
+    #[test]
+    fn test_viewport_fit() -> Result<()> {
+        let geo = [GeoPoint::new(0.0, 0.0)?, GeoPoint::new(5.0, 10.0)?];
+        for projection in [Projection::Mercator, Projection::Equirectangular] {
+            let view = Viewport::fit(projection, &geo, 400.0);
+            let (x1, y1) = view.to_pixel(geo[0]);
+            let (x2, y2) = view.to_pixel(geo[1]);
+
+            assert!((x1 - MARGIN).abs() < 1e-9);
+            assert!((x2 - (400.0 - MARGIN)).abs() < 1e-9);
+            assert!(y2 < y1); // north is up
+
+            // to_lat_lon is the inverse of to_pixel
+            let (lat, lon) = view.to_lat_lon(x2, y2);
+            assert!((lon - 10.0).abs() < 1e-9 && (lat - 5.0).abs() < 1e-9);
+        }
+
+        Ok(())
+    }
+
+    /// Tests that an empty trace and a width eaten by the margins are
+    /// refused instead of looping over infinite graticule bounds
+    #[test]
+    fn test_render_svg_degenerate() {
+        let err = render_svg(&[], &options()).unwrap_err();
+        assert!(err.to_string().contains("empty"));
+
+        let trace = [
+            TracePoint::new(1, 0.0, 0.0),
+            TracePoint::new(2, 500_000.0, 300_000.0),
+        ];
+        for width in [80.0, 40.0, -10.0, f64::NAN] {
+            let options = SvgOptions { width, ..options() };
+            let err = render_svg(&trace, &options).unwrap_err();
+            assert!(err.to_string().contains("more than 80 pixels wide"));
+        }
+
+        // Barely wider than the margins: a tiny scale, but a finite graticule
+        let options = SvgOptions {
+            width: 80.001,
+            ..options()
+        };
+        let svg = render_svg(&trace, &options).unwrap();
+        assert!(svg.contains("°</text>"));
+        assert!(!svg.contains("NaN"));
+    }
+
+    /// Tests that graticule lines stop at the bounds and skip invalid ones
+    #[test]
+    fn test_multiples() {
+        assert_eq!(
+            multiples(-7.0, 12.0, 5.0).collect::<Vec<_>>(),
+            vec![-5.0, 0.0, 5.0, 10.0]
+        );
+        assert_eq!(multiples(f64::MIN, f64::INFINITY, 5.0).count(), 0);
+        assert_eq!(multiples(0.0, 10.0, f64::NAN).count(), 0);
+        assert_eq!(multiples(0.0, 10.0, 0.0).count(), 0);
+    }
+
+    /// Tests the rounding helpers used by the scale bar and the graticule
+    #[test]
+    fn test_nice_values() {
+        assert_eq!(nice_distance(734.0), 500.0);
+        assert_eq!(nice_distance(1.9), 1.0);
+        assert_eq!(nice_distance(25.0), 20.0);
+        assert_eq!(graticule_step(8.0), 1.0);
+        assert_eq!(graticule_step(120.0), 15.0);
+    }
+
+    /// Tests the coastline file format with comments and several polylines
+    #[test]
+    fn test_load_coastline() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "# two islands")?;
+        writeln!(file, "0,0\n1,2\n\n\n5, 5\n6,6")?;
+
+        let lines = load_coastline(file.path().to_str().unwrap())?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][1], GeoPoint::new(2.0, 1.0)?); // "lon,lat" order
+        assert_eq!(lines[1].len(), 2);
+
+        // Latitudes beyond the poles are rejected with the line number
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "0,95")?;
+        let err = load_coastline(file.path().to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+
+        Ok(())
+    }
+}
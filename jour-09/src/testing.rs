@@ -0,0 +1,25 @@
+//! Trace fixtures shared by the unit tests
+
+use eyre::Result;
+
+use crate::geo::{GeoPoint, TracePoint};
+
+/// Builds a trace from (lat, lon) positions, with ids from 1
+pub fn trace(positions: &[(f64, f64)]) -> Result<Vec<TracePoint>> {
+    positions
+        .iter()
+        .zip(1..)
+        .map(|(&(lat, lon), id)| {
+            Ok(TracePoint {
+                id,
+                position: GeoPoint::new(lat, lon)?.to_mercator(),
+            })
+        })
+        .collect()
+}
+
+/// Points along the equator at the given longitudes, with ids from 1
+pub fn equator(longitudes: &[f64]) -> Result<Vec<TracePoint>> {
+    let positions: Vec<_> = longitudes.iter().map(|&lon| (0.0, lon)).collect();
+    trace(&positions)
+}
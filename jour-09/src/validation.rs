@@ -0,0 +1,521 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::geo::TracePoint;
+
+/// Trace points sorted by id
+pub type Trace = Vec<TracePoint>;
+
+/// What to do when the same id appears on several lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DuplicatePolicy {
+    /// Fail and list every duplicated id with its line numbers
+    #[default]
+    Reject,
+    /// Keep the point from the first line carrying the id
+    First,
+    /// Keep the point from the last line carrying the id
+    Last,
+}
+
+/// What to do when ids are missing from the sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GapPolicy {
+    /// Fail and list every missing range
+    Reject,
+    /// Keep the trace as is, gaps are only reported
+    #[default]
+    Keep,
+    /// Fill every missing id with a point linearly interpolated in Mercator space
+    Interpolate,
+}
+
+/// Largest gap filled by `GapPolicy::Interpolate`
+///
+/// A single mistyped id (2000000000 instead of 2000) would otherwise make the
+/// trace billions of points long.
+pub const MAX_INTERPOLATED_GAP: i64 = 1_000_000;
+
+/// Combined data-quality policy applied after parsing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationPolicy {
+    pub duplicates: DuplicatePolicy,
+    pub gaps: GapPolicy,
+}
+
+/// An id found on two different lines (1-based line numbers)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub id: i32,
+    pub first_line: usize,
+    pub second_line: usize,
+}
+
+/// An inclusive range of ids missing from the sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gap {
+    pub from: i32,
+    pub to: i32,
+}
+
+/// Data-quality issues found in a trace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub duplicates: Vec<Duplicate>,
+    pub gaps: Vec<Gap>,
+}
+
+impl Gap {
+    /// Number of missing ids
+    pub fn missing_ids(&self) -> i64 {
+        i64::from(self.to) - i64::from(self.from) + 1
+    }
+
+    /// Gap between two consecutive ids, if any (computed in i64, the
+    /// difference of two extreme ids does not fit in an i32)
+    fn between(previous: i32, next: i32) -> Option<Self> {
+        (i64::from(next) - i64::from(previous) > 1).then(|| Gap {
+            from: previous + 1,
+            to: next - 1,
+        })
+    }
+
+    /// Fails when the gap is too large to be interpolated
+    fn check_interpolable(&self) -> eyre::Result<()> {
+        if self.missing_ids() > MAX_INTERPOLATED_GAP {
+            eyre::bail!(
+                "Cannot interpolate {}: {} ids is more than the limit of {}",
+                self,
+                self.missing_ids(),
+                MAX_INTERPOLATED_GAP
+            );
+        }
+        Ok(())
+    }
+}
+
+impl ValidationReport {
+    /// True when the trace has neither duplicates nor gaps
+    pub fn is_clean(&self) -> bool {
+        self.duplicates.is_empty() && self.gaps.is_empty()
+    }
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "duplicate id {} at lines {} and {}",
+            self.id, self.first_line, self.second_line
+        )
+    }
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.from == self.to {
+            write!(f, "missing id {}", self.from)
+        } else {
+            write!(f, "missing ids {}..={}", self.from, self.to)
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let issues: Vec<String> = self
+            .duplicates
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.gaps.iter().map(ToString::to_string))
+            .collect();
+        write!(f, "{}", issues.join(", "))
+    }
+}
+
+/// Checks a parsed trace for duplicate ids and gaps, then applies the policy
+///
+/// Input: (line, point) records in file order
+/// Output: points sorted by id with the policy applied, and the issues found
+///
+/// # Errors
+/// Returns an error listing the offending ids when the policy is `Reject`
+pub fn validate(
+    records: Vec<(usize, TracePoint)>,
+    policy: &ValidationPolicy,
+) -> eyre::Result<(Trace, ValidationReport)> {
+    let mut report = ValidationReport::default();
+
+    // Remember the first line each id was seen on, and the point kept for it
+    let mut seen: HashMap<i32, usize> = HashMap::with_capacity(records.len());
+    let mut kept: HashMap<i32, TracePoint> = HashMap::with_capacity(records.len());
+
+    for (line, point) in records {
+        let id = point.id;
+        match seen.get(&id) {
+            Some(&first_line) => {
+                report.duplicates.push(Duplicate {
+                    id,
+                    first_line,
+                    second_line: line,
+                });
+                // Only the "last" policy overwrites the point already kept
+                if policy.duplicates == DuplicatePolicy::Last {
+                    kept.insert(id, point);
+                }
+            }
+            None => {
+                seen.insert(id, line);
+                kept.insert(id, point);
+            }
+        }
+    }
+
+    if policy.duplicates == DuplicatePolicy::Reject && !report.duplicates.is_empty() {
+        let list: Vec<String> = report.duplicates.iter().map(ToString::to_string).collect();
+        eyre::bail!("Duplicate ids in trace: {}", list.join(", "));
+    }
+
+    // Sort entries by id to ensure chronological/sequential order
+    let mut data: Trace = kept.into_values().collect();
+    data.sort_by_key(|k| k.id);
+
+    // Any jump of more than one between consecutive ids is a gap
+    report.gaps = data
+        .windows(2)
+        .filter_map(|w| Gap::between(w[0].id, w[1].id))
+        .collect();
+
+    match policy.gaps {
+        GapPolicy::Reject if !report.gaps.is_empty() => {
+            let list: Vec<String> = report.gaps.iter().map(ToString::to_string).collect();
+            eyre::bail!("Missing ids in trace: {}", list.join(", "));
+        }
+        GapPolicy::Interpolate if !report.gaps.is_empty() => {
+            for gap in &report.gaps {
+                gap.check_interpolable()?;
+            }
+            data = interpolate_gaps(&data);
+        }
+        _ => {}
+    }
+
+    Ok((data, report))
+}
+
+/// Fills every missing id between two known points with a linear interpolation
+///
+/// The caller checks the gaps against `MAX_INTERPOLATED_GAP` first.
+fn interpolate_gaps(data: &[TracePoint]) -> Trace {
+    let mut filled = Vec::with_capacity(data.len());
+
+    for w in data.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        filled.push(a);
+
+        // t goes from 0 (first point) to 1 (second point) over the missing ids
+        let span = (i64::from(b.id) - i64::from(a.id)) as f64;
+        for id in a.id + 1..b.id {
+            let t = f64::from(id - a.id) / span;
+            filled.push(TracePoint::new(
+                id,
+                a.position.x + (b.position.x - a.position.x) * t,
+                a.position.y + (b.position.y - a.position.y) * t,
+            ));
+        }
+    }
+
+    if let Some(&last) = data.last() {
+        filled.push(last);
+    }
+
+    filled
+}
+
+/// Streaming counterpart of `validate` for records already sorted by (id, line)
+///
+/// Duplicates are then adjacent and gaps show between consecutive ids, so the
+/// policy is applied with a single pending point instead of whole-trace maps.
+/// The accepted points, interpolated ones included, go to the `emit` callback
+/// in id order.
+pub struct StreamValidator {
+    policy: ValidationPolicy,
+    report: ValidationReport,
+    /// Line of the first occurrence of the current id, and the point kept for it
+    pending: Option<(usize, TracePoint)>,
+    /// Last point handed to `emit`
+    previous: Option<TracePoint>,
+    /// First gap too large to interpolate, reported by `finish`
+    oversized: Option<Gap>,
+}
+
+impl StreamValidator {
+    pub fn new(policy: ValidationPolicy) -> Self {
+        Self {
+            policy,
+            report: ValidationReport::default(),
+            pending: None,
+            previous: None,
+            oversized: None,
+        }
+    }
+
+    /// Feeds the next record, in (id, line) order
+    pub fn push(&mut self, line: usize, point: TracePoint, emit: &mut impl FnMut(TracePoint)) {
+        match self.pending {
+            Some((first_line, ref mut kept)) if kept.id == point.id => {
+                self.report.duplicates.push(Duplicate {
+                    id: point.id,
+                    first_line,
+                    second_line: line,
+                });
+                if self.policy.duplicates == DuplicatePolicy::Last {
+                    *kept = point;
+                }
+            }
+            _ => {
+                if let Some((_, kept)) = self.pending.replace((line, point)) {
+                    self.accept(kept, emit);
+                }
+            }
+        }
+    }
+
+    /// Flushes the last point and returns the issues found
+    ///
+    /// # Errors
+    /// Returns the same errors as `validate` when the policy is `Reject`
+    pub fn finish(mut self, emit: &mut impl FnMut(TracePoint)) -> eyre::Result<ValidationReport> {
+        if let Some((_, kept)) = self.pending.take() {
+            self.accept(kept, emit);
+        }
+
+        if self.policy.duplicates == DuplicatePolicy::Reject && !self.report.duplicates.is_empty() {
+            // Same order as `validate`: by line of the repeated occurrence
+            self.report.duplicates.sort_by_key(|d| d.second_line);
+            let list: Vec<String> = self
+                .report
+                .duplicates
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            eyre::bail!("Duplicate ids in trace: {}", list.join(", "));
+        }
+        if self.policy.gaps == GapPolicy::Reject && !self.report.gaps.is_empty() {
+            let list: Vec<String> = self.report.gaps.iter().map(ToString::to_string).collect();
+            eyre::bail!("Missing ids in trace: {}", list.join(", "));
+        }
+        if let Some(gap) = &self.oversized {
+            gap.check_interpolable()?;
+        }
+
+        self.report.duplicates.sort_by_key(|d| d.second_line);
+        Ok(self.report)
+    }
+
+    /// Emits a point whose duplicates have all been seen, filling the gap before it
+    fn accept(&mut self, point: TracePoint, emit: &mut impl FnMut(TracePoint)) {
+        if let Some(previous) = self.previous
+            && let Some(gap) = Gap::between(previous.id, point.id)
+        {
+            let interpolate = self.policy.gaps == GapPolicy::Interpolate;
+            let oversized = gap.check_interpolable().is_err();
+            if interpolate && oversized && self.oversized.is_none() {
+                self.oversized = Some(gap.clone());
+            }
+            self.report.gaps.push(gap);
+            if interpolate && !oversized {
+                // Skip the two ends, they are emitted on their own
+                let filled = interpolate_gaps(&[previous, point]);
+                for &inserted in &filled[1..filled.len() - 1] {
+                    emit(inserted);
+                }
+            }
+        }
+
+        emit(point);
+        self.previous = Some(point);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds (line, point) records with line numbers following the input order
+    fn records(ids: &[i32]) -> Vec<(usize, TracePoint)> {
+        ids.iter()
+            .enumerate()
+            .map(|(i, &id)| {
+                (
+                    i + 1,
+                    TracePoint::new(id, f64::from(id) * 10.0, f64::from(i as i32)),
+                )
+            })
+            .collect()
+    }
+
+    /// Tests that a clean sequence produces an empty report
+    #[test]
+    fn test_validate_clean() -> eyre::Result<()> {
+        let (data, report) = validate(records(&[2, 1, 3]), &ValidationPolicy::default())?;
+
+        assert!(report.is_clean());
+        assert_eq!(data.iter().map(|p| p.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        Ok(())
+    }
+
+    /// Tests that duplicates are rejected by default, with both line numbers
+    #[test]
+    fn test_validate_duplicate_rejected() {
+        let err = validate(records(&[1, 2, 1]), &ValidationPolicy::default()).unwrap_err();
+
+        assert!(err.to_string().contains("duplicate id 1 at lines 1 and 3"));
+    }
+
+    /// Tests the "first" and "last" duplicate policies
+    ///
+    /// Id 1 appears on line 1 (y = 0.0) and on line 3 (y = 2.0).
+    #[test]
+    fn test_validate_duplicate_first_and_last() -> eyre::Result<()> {
+        let first = ValidationPolicy {
+            duplicates: DuplicatePolicy::First,
+            ..Default::default()
+        };
+        let (data, report) = validate(records(&[1, 2, 1]), &first)?;
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(data[0], TracePoint::new(1, 10.0, 0.0));
+
+        let last = ValidationPolicy {
+            duplicates: DuplicatePolicy::Last,
+            ..Default::default()
+        };
+        let (data, _) = validate(records(&[1, 2, 1]), &last)?;
+        assert_eq!(data[0], TracePoint::new(1, 10.0, 2.0));
+
+        Ok(())
+    }
+
+    /// Tests that missing ranges are reported, and rejected on demand
+    #[test]
+    fn test_validate_gaps() -> eyre::Result<()> {
+        let (_, report) = validate(records(&[1, 2, 5, 7]), &ValidationPolicy::default())?;
+        assert_eq!(
+            report.gaps,
+            vec![Gap { from: 3, to: 4 }, Gap { from: 6, to: 6 }]
+        );
+
+        let reject = ValidationPolicy {
+            gaps: GapPolicy::Reject,
+            ..Default::default()
+        };
+        let err = validate(records(&[1, 2, 5]), &reject).unwrap_err();
+        assert!(err.to_string().contains("missing ids 3..=4"));
+
+        Ok(())
+    }
+
+    /// Tests that the streaming validator matches `validate` for every policy
+    #[test]
+    fn test_stream_validator_matches_validate() {
+        let ids = [4, 1, 2, 9, 2, 7, 4, 4, 12];
+
+        for duplicates in [
+            DuplicatePolicy::Reject,
+            DuplicatePolicy::First,
+            DuplicatePolicy::Last,
+        ] {
+            for gaps in [GapPolicy::Reject, GapPolicy::Keep, GapPolicy::Interpolate] {
+                let policy = ValidationPolicy { duplicates, gaps };
+                let expected = validate(records(&ids), &policy);
+
+                let mut sorted = records(&ids);
+                sorted.sort_by_key(|&(line, point)| (point.id, line));
+                let mut streamed = Vec::new();
+                let mut validator = StreamValidator::new(policy);
+                for (line, point) in sorted {
+                    validator.push(line, point, &mut |p| streamed.push(p));
+                }
+                let report = validator.finish(&mut |p| streamed.push(p));
+
+                match (expected, report) {
+                    (Ok((data, expected)), Ok(report)) => {
+                        assert_eq!(streamed, data);
+                        assert_eq!(report, expected);
+                    }
+                    (Err(expected), Err(err)) => assert_eq!(err.to_string(), expected.to_string()),
+                    (expected, report) => panic!("{:?} vs {:?}", expected, report),
+                }
+            }
+        }
+    }
+
+    /// Tests that gaps between extreme ids neither overflow nor get
+    /// interpolated point by point
+    #[test]
+    fn test_validate_huge_gap() -> eyre::Result<()> {
+        let ids = [i32::MIN, 1, 2_000_000_000, i32::MAX];
+        let (_, report) = validate(records(&ids), &ValidationPolicy::default())?;
+        assert_eq!(report.gaps.len(), 3);
+        assert_eq!(report.gaps[0].missing_ids(), 2_147_483_648);
+
+        // Kept gaps are only reported, whatever their size
+        let mut validator = StreamValidator::new(ValidationPolicy::default());
+        for (line, point) in records(&ids) {
+            validator.push(line, point, &mut |_| {});
+        }
+        assert_eq!(validator.finish(&mut |_| {})?, report);
+
+        let policy = ValidationPolicy {
+            gaps: GapPolicy::Interpolate,
+            ..Default::default()
+        };
+        let err = validate(records(&[1, 2_000_000_000]), &policy).unwrap_err();
+        assert!(err.to_string().contains(
+            "Cannot interpolate missing ids 2..=1999999999: 1999999998 ids is more than the limit"
+        ));
+
+        let mut validator = StreamValidator::new(policy);
+        let mut streamed = 0;
+        for (line, point) in records(&[1, 2_000_000_000]) {
+            validator.push(line, point, &mut |_| streamed += 1);
+        }
+        let err = validator.finish(&mut |_| streamed += 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            validate(records(&[1, 2_000_000_000]), &policy)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(streamed, 2);
+
+        Ok(())
+    }
+
+    /// Tests that interpolation fills the gap linearly between its neighbours
+    ///
+    /// Point 1 is at (10, 0) and point 3 at (30, 1): the filled id 2 must sit
+    /// halfway, at (20, 0.5).
+    #[test]
+    fn test_validate_interpolate() -> eyre::Result<()> {
+        let policy = ValidationPolicy {
+            gaps: GapPolicy::Interpolate,
+            ..Default::default()
+        };
+        let (data, report) = validate(records(&[1, 3]), &policy)?;
+
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(
+            data,
+            vec![
+                TracePoint::new(1, 10.0, 0.0),
+                TracePoint::new(2, 20.0, 0.5),
+                TracePoint::new(3, 30.0, 1.0)
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! Benchmark de l'ensemble compact contre un `HashSet<(i32, i32)>`
+//!
+//! Lancer avec `cargo bench --bench houses`
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::collections::HashSet;
+use std::hint::black_box;
+
+use jour_14::houses::HouseSet;
+use jour_14::xorshift::XorShift;
+
+/// Maisons d'une marche aléatoire de `len` pas, diagonales comprises
+fn walk(len: usize) -> Vec<(i32, i32)> {
+    let mut random = XorShift::default();
+    let mut house = (0, 0);
+    (0..len)
+        .map(|_| {
+            let (dx, dy) = random.direction().delta();
+            house = (house.0 + dx, house.1 + dy);
+            house
+        })
+        .collect()
+}
+
+fn bench_house_set(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_walk");
+    group.sample_size(10);
+    for steps in [100_000, 1_000_000, 10_000_000] {
+        let houses = walk(steps);
+
+        group.bench_with_input(BenchmarkId::new("HouseSet", steps), &houses, |b, houses| {
+            b.iter(|| {
+                let mut set = HouseSet::new();
+                for &house in houses {
+                    set.insert(house);
+                }
+                black_box(set.len())
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("HashSet", steps), &houses, |b, houses| {
+            b.iter(|| {
+                let mut set = HashSet::new();
+                for &house in houses {
+                    set.insert(house);
+                }
+                black_box(set.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_house_set);
+criterion_main!(benches);
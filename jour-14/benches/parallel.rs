@@ -0,0 +1,39 @@
+//! Benchmark du comptage parallèle contre le comptage séquentiel
+//!
+//! Lancer avec `cargo bench --bench parallel`
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::thread;
+
+use jour_14::obstacles::Obstacles;
+use jour_14::xorshift::XorShift;
+use jour_14::{count_unique_houses, parallel};
+
+fn bench_parallel(c: &mut Criterion) {
+    let program = XorShift::default().program(2_000_000);
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+
+    let mut group = c.benchmark_group("count_unique_houses");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| black_box(count_unique_houses(&program, &Obstacles::default()).unwrap()))
+    });
+    let mut chunks = vec![1, 2, 4, 8];
+    if !chunks.contains(&cores) {
+        chunks.push(cores);
+    }
+    for chunks in chunks {
+        group.bench_with_input(
+            BenchmarkId::new("parallel", chunks),
+            &chunks,
+            |b, &chunks| {
+                b.iter(|| black_box(parallel::count_unique_houses(&program, chunks).unwrap()))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parallel);
+criterion_main!(benches);
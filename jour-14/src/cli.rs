@@ -0,0 +1,122 @@
+use clap::{Parser, Subcommand};
+
+use eyre::Result;
+
+use crate::instructions::{ParseOptions, Syntax};
+use crate::obstacles::{BlockedPolicy, ObstacleMap, Obstacles};
+
+/// Structure de l'interface en ligne de commande
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Comptage des maisons visitées",
+    long_about = "Compte les maisons uniques visitées par une équipe de livreurs qui suivent les instructions du fichier à tour de rôle."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Fichier d'instructions (N, S, E, W)
+    #[arg(short, long, global = true, default_value = "steps")]
+    pub input: String,
+
+    /// Syntaxe des instructions
+    #[arg(long, global = true, value_enum, default_value_t = Syntax::Plain)]
+    pub syntax: Syntax,
+
+    /// Ignore les caractères invalides (en les listant) au lieu d'échouer
+    #[arg(long, global = true)]
+    pub skip_invalid: bool,
+
+    /// Carte des obstacles ('#' bloqué, '.' libre, 'H' départ, nord en haut)
+    #[arg(long, global = true)]
+    pub map: Option<String>,
+
+    /// Conduite à tenir quand un pas mène sur une maison bloquée
+    #[arg(long, global = true, value_enum, default_value_t = BlockedPolicy::Fail)]
+    pub on_blocked: BlockedPolicy,
+
+    /// Nombre de livreurs qui se partagent les instructions à tour de rôle
+    /// (un seul pour la carte des passages et la relecture)
+    #[arg(short = 'k', long, global = true, default_value_t = 1)]
+    pub couriers: usize,
+
+    /// Affiche les statistiques de fréquentation (maisons les plus visitées,
+    /// histogramme, premier retour, maison la plus éloignée), comptage seulement
+    #[arg(long, global = true)]
+    pub stats: bool,
+
+    /// Nombre de maisons les plus visitées listées avec --stats
+    #[arg(long, global = true, requires = "stats", default_value_t = 5)]
+    pub top: usize,
+
+    /// Compte en parallèle en découpant les instructions en MORCEAUX (un par
+    /// cœur par défaut, parcourus par au plus un fil par cœur), sans obstacles,
+    /// comptage seulement
+    #[arg(long, global = true, value_name = "MORCEAUX")]
+    pub parallel: Option<Option<usize>>,
+}
+
+/// Sorties disponibles en plus du comptage
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Compte les maisons visitées (par défaut)
+    Count,
+    /// Dessine la carte des passages, dans le terminal ou dans un fichier PNG/SVG
+    Heatmap {
+        /// Image à écrire (.png ou .svg), dans le terminal si absent
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Taille d'une maison en pixels dans l'image
+        #[arg(long, default_value_t = 4)]
+        scale: u32,
+
+        /// Largeur de la carte en caractères (largeur du terminal par défaut)
+        #[arg(long)]
+        columns: Option<usize>,
+
+        /// Hauteur de la carte en lignes (hauteur du terminal par défaut)
+        #[arg(long)]
+        rows: Option<usize>,
+
+        /// Désactive les couleurs, les passages sont alors nuancés par ░▒▓█
+        #[arg(long)]
+        no_color: bool,
+    },
+    /// Rejoue la tournée pas à pas dans le terminal
+    Replay {
+        /// Pas joués par seconde (modifiable avec + et - pendant la relecture)
+        #[arg(long, default_value_t = 20.0)]
+        speed: f64,
+
+        /// Démarre en pause (espace pour lancer, n pour avancer d'un pas)
+        #[arg(long)]
+        paused: bool,
+
+        /// Désactive les couleurs, les passages sont alors nuancés par ░▒▓█
+        #[arg(long)]
+        no_color: bool,
+    },
+}
+
+impl Cli {
+    /// Options d'analyse des instructions
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            syntax: self.syntax,
+            skip_invalid: self.skip_invalid,
+        }
+    }
+
+    /// Charge la carte des obstacles s'il y en a une (aucun obstacle sinon)
+    pub fn obstacles(&self) -> Result<Obstacles> {
+        Ok(Obstacles {
+            map: match &self.map {
+                Some(path) => ObstacleMap::load(path)?,
+                None => ObstacleMap::default(),
+            },
+            policy: self.on_blocked,
+        })
+    }
+}
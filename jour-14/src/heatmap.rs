@@ -0,0 +1,466 @@
+use crossterm::style::{Color, Stylize};
+use eyre::{Result, WrapErr};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+
+use crate::visits::{House, Visits};
+
+/// Couleurs de l'échelle, des maisons peu visitées (bleu) aux plus visitées (rouge)
+const SCALE: [(u8, u8, u8); 5] = [
+    (49, 54, 149),
+    (69, 117, 180),
+    (254, 224, 144),
+    (244, 109, 67),
+    (165, 0, 38),
+];
+
+/// Caractères utilisés sans couleur, du moins au plus visité
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+/// Limite de taille des images PNG, en pixels par côté
+const MAX_PNG_SIDE: u64 = 16_384;
+
+/// Rectangle englobant les maisons visitées, nord en haut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min_x: i32,
+    max_y: i32,
+    /// Nombre de colonnes et de lignes de maisons
+    width: u64,
+    height: u64,
+}
+
+impl Bounds {
+    fn of(visits: &Visits) -> Self {
+        let (min_x, max_x, min_y, max_y) = visits.counts.keys().fold(
+            (i32::MAX, i32::MIN, i32::MAX, i32::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+        Bounds {
+            min_x,
+            max_y,
+            width: (i64::from(max_x) - i64::from(min_x) + 1) as u64,
+            height: (i64::from(max_y) - i64::from(min_y) + 1) as u64,
+        }
+    }
+
+    /// Colonne et ligne d'une maison, la ligne 0 étant la plus au nord
+    fn cell(&self, (x, y): House) -> (u64, u64) {
+        (
+            (i64::from(x) - i64::from(self.min_x)) as u64,
+            (i64::from(self.max_y) - i64::from(y)) as u64,
+        )
+    }
+}
+
+/// Position d'un nombre de passages sur l'échelle, entre 0 et 1
+///
+/// L'échelle est logarithmique: quelques maisons très visitées n'écrasent
+/// pas les différences entre les maisons visitées une ou deux fois.
+fn intensity(count: u32, max: u32) -> f64 {
+    if max <= 1 {
+        return 1.0;
+    }
+    f64::from(count).ln() / f64::from(max).ln()
+}
+
+/// Couleur d'un nombre de passages, interpolée linéairement entre les
+/// couleurs de `SCALE`
+pub fn heat_color(count: u32, max: u32) -> (u8, u8, u8) {
+    let t = intensity(count, max).clamp(0.0, 1.0) * (SCALE.len() - 1) as f64;
+    let index = (t.floor() as usize).min(SCALE.len() - 2);
+    let f = t - index as f64;
+    let (a, b) = (SCALE[index], SCALE[index + 1]);
+    let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * f).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// Couleur de terminal d'un triplet RGB
+pub fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// Caractère de nuance d'un nombre de passages, pour l'affichage sans couleur
+pub fn shade(count: u32, max: u32) -> char {
+    let level = (intensity(count, max) * (SHADES.len() - 1) as f64).round() as usize;
+    SHADES[level.min(SHADES.len() - 1)]
+}
+
+/// Dessine la carte des passages dans un terminal de `columns` x `rows` caractères
+///
+/// Chaque caractère représente deux pixels superposés (demi-blocs `▀`/`▄`
+/// colorés). Si le quartier visité est plus grand que le terminal, chaque
+/// pixel regroupe un carré de maisons et prend le nombre de passages de la
+/// plus visitée. Le départ est marqué `H`, l'arrivée `F`.
+/// Sans couleur, chaque caractère est un pixel nuancé par `░▒▓█`.
+pub fn render_terminal(visits: &Visits, columns: usize, rows: usize, color: bool) -> String {
+    let bounds = Bounds::of(visits);
+    let columns = columns.max(1) as u64;
+    // Avec les demi-blocs, une ligne de texte contient deux lignes de pixels
+    let pixel_rows = rows.max(1) as u64 * if color { 2 } else { 1 };
+
+    // Maisons par pixel, identique en largeur et en hauteur pour garder les proportions
+    let factor = bounds
+        .width
+        .div_ceil(columns)
+        .max(bounds.height.div_ceil(pixel_rows))
+        .max(1);
+    let width = bounds.width.div_ceil(factor) as usize;
+    let height = bounds.height.div_ceil(factor) as usize;
+
+    // Nombre de passages de la maison la plus visitée de chaque pixel
+    let mut pixels = vec![0u32; width * height];
+    for (&house, &count) in &visits.counts {
+        let (column, row) = bounds.cell(house);
+        let pixel = &mut pixels[(row / factor) as usize * width + (column / factor) as usize];
+        *pixel = (*pixel).max(count);
+    }
+    let max = visits.counts.values().copied().max().unwrap_or(0);
+
+    // Caractère de chaque marqueur; l'arrivée est posée en dernier et l'emporte
+    let marker_at = |house: House, label: char| {
+        let (column, row) = bounds.cell(house);
+        let row = row / factor;
+        let text_row = if color { row / 2 } else { row };
+        ((column / factor) as usize, text_row as usize, label)
+    };
+    let markers = [
+        marker_at((0, 0), 'H'),
+        marker_at(visits.final_position, 'F'),
+    ];
+
+    let text_rows = if color { height.div_ceil(2) } else { height };
+    let mut out = String::new();
+    for text_row in 0..text_rows {
+        for column in 0..width {
+            if let Some(&(_, _, label)) = markers
+                .iter()
+                .rev()
+                .find(|&&(c, r, _)| c == column && r == text_row)
+            {
+                if color {
+                    out.push_str(&label.with(Color::White).on(Color::Black).bold().to_string());
+                } else {
+                    out.push(label);
+                }
+                continue;
+            }
+
+            if !color {
+                match pixels[text_row * width + column] {
+                    0 => out.push(' '),
+                    count => out.push(shade(count, max)),
+                }
+                continue;
+            }
+
+            let top = pixels[2 * text_row * width + column];
+            let bottom = if 2 * text_row + 1 < height {
+                pixels[(2 * text_row + 1) * width + column]
+            } else {
+                0
+            };
+            let cell = match (top, bottom) {
+                (0, 0) => " ".to_string(),
+                (top, 0) => '▀'.with(rgb(heat_color(top, max))).to_string(),
+                (0, bottom) => '▄'.with(rgb(heat_color(bottom, max))).to_string(),
+                (top, bottom) => '▀'
+                    .with(rgb(heat_color(top, max)))
+                    .on(rgb(heat_color(bottom, max)))
+                    .to_string(),
+            };
+            out.push_str(&cell);
+        }
+        out.push('\n');
+    }
+
+    // Légende: échelle, marqueurs et taille des pixels
+    let legend: String = if color {
+        (0..=8u32)
+            .map(|step| {
+                let count = (f64::from(max.max(1)).powf(f64::from(step) / 8.0)).round() as u32;
+                '█'.with(rgb(heat_color(count, max))).to_string()
+            })
+            .collect()
+    } else {
+        SHADES.iter().collect()
+    };
+    let _ = writeln!(
+        out,
+        "1 {} {} passages   H départ, F arrivée   1 pixel = {}x{} maisons",
+        legend, max, factor, factor
+    );
+    out
+}
+
+/// Format d'image, choisi d'après l'extension du fichier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    /// Reconnaît l'extension `.png` ou `.svg`, sans tenir compte de la casse
+    pub fn from_path(path: &str) -> Result<Self> {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".png") {
+            Ok(ImageFormat::Png)
+        } else if lower.ends_with(".svg") {
+            Ok(ImageFormat::Svg)
+        } else {
+            eyre::bail!(
+                "Format d'image inconnu pour '{}' (attendu .png ou .svg)",
+                path
+            )
+        }
+    }
+}
+
+/// Écrit la carte des passages dans un fichier PNG ou SVG selon son extension,
+/// avec `scale` pixels par maison
+pub fn write_image(visits: &Visits, path: &str, scale: u32) -> Result<()> {
+    match ImageFormat::from_path(path)? {
+        ImageFormat::Svg => std::fs::write(path, render_svg(visits, scale))
+            .wrap_err_with(|| format!("Impossible d'écrire '{}'", path)),
+        ImageFormat::Png => write_png(visits, path, scale),
+    }
+}
+
+/// Hauteur de la légende sous la carte, en pixels
+const LEGEND_HEIGHT: u64 = 24;
+
+/// Rend la carte des passages en document SVG autonome
+///
+/// Une maison visitée est un carré de `scale` pixels coloré selon l'échelle;
+/// la légende sous la carte donne l'échelle de 1 au maximum de passages.
+pub fn render_svg(visits: &Visits, scale: u32) -> String {
+    let bounds = Bounds::of(visits);
+    let scale = u64::from(scale.max(1));
+    let max = visits.counts.values().copied().max().unwrap_or(0);
+    let (width, height) = (bounds.width * scale, bounds.height * scale);
+    // La légende a besoin d'un minimum de place pour ses libellés
+    let canvas_width = width.max(200);
+
+    let mut svg = String::new();
+    // Écrire dans une String ne peut pas échouer, d'où les résultats ignorés
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="10">"#,
+        w = canvas_width,
+        h = height + LEGEND_HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#f4f4f4"/>"##
+    );
+
+    // Ordre stable pour des fichiers reproductibles
+    let mut houses: Vec<(&House, &u32)> = visits.counts.iter().collect();
+    houses.sort_unstable();
+    for (&house, &count) in houses {
+        let (column, row) = bounds.cell(house);
+        let (r, g, b) = heat_color(count, max);
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="{scale}" height="{scale}" fill="#{r:02x}{g:02x}{b:02x}"><title>({}, {}) : {count}</title></rect>"##,
+            column * scale,
+            row * scale,
+            house.0,
+            house.1
+        );
+    }
+
+    // Marqueurs du départ et de l'arrivée
+    for (house, fill, label) in [
+        ((0, 0), "#1a9850", "départ"),
+        (visits.final_position, "#000000", "arrivée"),
+    ] {
+        let (column, row) = bounds.cell(house);
+        let radius = (scale as f64 * 0.8).max(3.0);
+        let (cx, cy) = (
+            (column as f64 + 0.5) * scale as f64,
+            (row as f64 + 0.5) * scale as f64,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{cx:.1}" cy="{cy:.1}" r="{radius:.1}" fill="{fill}" stroke="white" stroke-width="1"><title>{label}</title></circle>"#
+        );
+    }
+
+    // Échelle de couleurs, du bleu (1 passage) au rouge (maximum)
+    let _ = writeln!(
+        svg,
+        r#"<defs><linearGradient id="scale">{}</linearGradient></defs>"#,
+        SCALE
+            .iter()
+            .enumerate()
+            .map(|(i, (r, g, b))| format!(
+                r##"<stop offset="{:.2}" stop-color="#{r:02x}{g:02x}{b:02x}"/>"##,
+                i as f64 / (SCALE.len() - 1) as f64
+            ))
+            .collect::<String>()
+    );
+    let bar_y = height + 6;
+    let _ = writeln!(
+        svg,
+        r#"<text x="4" y="{}">1</text><rect x="14" y="{bar_y}" width="120" height="10" fill="url(#scale)"/><text x="140" y="{}">{max} passages</text>"#,
+        bar_y + 9,
+        bar_y + 9
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Écrit la carte des passages en PNG, avec la légende en dégradé sous la carte
+fn write_png(visits: &Visits, path: &str, scale: u32) -> Result<()> {
+    let bounds = Bounds::of(visits);
+    let scale = u64::from(scale.max(1));
+    let max = visits.counts.values().copied().max().unwrap_or(0);
+    let (map_width, map_height) = (bounds.width * scale, bounds.height * scale);
+    let (width, height) = (map_width.max(64), map_height + LEGEND_HEIGHT);
+    if width > MAX_PNG_SIDE || height > MAX_PNG_SIDE {
+        eyre::bail!(
+            "Image trop grande ({}x{} pixels, au plus {} par côté), réduisez --scale ou utilisez le SVG",
+            width,
+            height,
+            MAX_PNG_SIDE
+        );
+    }
+
+    let (w, h) = (width as usize, height as usize);
+    let mut pixels = vec![0xf4u8; w * h * 3];
+    let mut fill = |x0: u64, y0: u64, size_x: u64, size_y: u64, (r, g, b): (u8, u8, u8)| {
+        for y in y0..(y0 + size_y).min(height) {
+            for x in x0..(x0 + size_x).min(width) {
+                let i = (y as usize * w + x as usize) * 3;
+                pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    };
+
+    for (&house, &count) in &visits.counts {
+        let (column, row) = bounds.cell(house);
+        fill(
+            column * scale,
+            row * scale,
+            scale,
+            scale,
+            heat_color(count, max),
+        );
+    }
+
+    // Marqueurs: carrés pleins débordant d'un pixel autour de la maison
+    for (house, color) in [((0, 0), (26, 152, 80)), (visits.final_position, (0, 0, 0))] {
+        let (column, row) = bounds.cell(house);
+        let (x, y) = (
+            (column * scale).saturating_sub(1),
+            (row * scale).saturating_sub(1),
+        );
+        fill(x, y, scale + 2, scale + 2, color);
+    }
+
+    // Légende: dégradé de 1 passage (gauche) au maximum (droite)
+    let bar_width = width - 8;
+    for i in 0..bar_width {
+        let count = f64::from(max.max(1)).powf(i as f64 / (bar_width - 1) as f64);
+        fill(
+            4 + i,
+            map_height + 8,
+            1,
+            10,
+            heat_color(count.round() as u32, max),
+        );
+    }
+
+    let file = File::create(path).wrap_err_with(|| format!("Impossible de créer '{}'", path))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .wrap_err_with(|| format!("Impossible d'écrire '{}'", path))
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{Syntax, parse};
+    use crate::obstacles::Obstacles;
+    use crate::visits::record;
+
+    fn visits(text: &str) -> Visits {
+        record(
+            &parse(text, Syntax::Extended).unwrap(),
+            &Obstacles::default(),
+        )
+        .unwrap()
+    }
+
+    /// L'échelle va du bleu au rouge
+    #[test]
+    fn test_heat_color() {
+        assert_eq!(heat_color(1, 100), SCALE[0]);
+        assert_eq!(heat_color(100, 100), SCALE[SCALE.len() - 1]);
+        assert_eq!(heat_color(1, 1), SCALE[SCALE.len() - 1]);
+    }
+
+    /// Sans couleur: un caractère par maison, nord en haut, marqueurs H et F
+    /// E2 N S: (0,0) -> (1,0) -> (2,0) -> (2,1) -> (2,0)
+    #[test]
+    fn test_render_terminal_plain() {
+        let map = render_terminal(&visits("E2 N S"), 10, 10, false);
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(lines[0], "  ░");
+        assert_eq!(lines[1], "H░F");
+        assert!(lines[2].contains("1 pixel = 1x1 maisons"));
+    }
+
+    /// Un quartier plus grand que le terminal est réduit
+    #[test]
+    fn test_render_terminal_downsampled() {
+        let map = render_terminal(&visits("E99"), 10, 10, false);
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(lines[0].chars().count(), 10);
+        assert!(lines[1].contains("1 pixel = 10x10 maisons"));
+    }
+
+    /// Le SVG contient une case par maison, les deux marqueurs et la légende
+    #[test]
+    fn test_render_svg() {
+        let svg = render_svg(&visits("NNSS"), 4);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect x=").count(), 3 + 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains("2 passages"));
+    }
+
+    /// Le format est choisi d'après l'extension
+    #[test]
+    fn test_image_format() {
+        assert_eq!(ImageFormat::from_path("a.PNG").unwrap(), ImageFormat::Png);
+        assert_eq!(ImageFormat::from_path("a.svg").unwrap(), ImageFormat::Svg);
+        assert!(ImageFormat::from_path("a.jpg").is_err());
+    }
+
+    /// Le PNG écrit est lisible et a la taille attendue
+    #[test]
+    fn test_write_png() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("heatmap.png");
+        let path = path.to_str().unwrap();
+        write_image(&visits("E99 N"), path, 2)?;
+
+        let decoder = png::Decoder::new(std::io::BufReader::new(File::open(path)?));
+        let reader = decoder.read_info()?;
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (200, 4 + 24));
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::visits::House;
+
+/// Côté d'une tuile, en maisons (une ligne de tuile tient dans un `u64`)
+const TILE_SIDE: i32 = 64;
+/// Décalage qui donne la tuile d'une coordonnée
+const TILE_SHIFT: u32 = TILE_SIDE.trailing_zeros();
+/// Position dans la tuile d'une coordonnée
+const IN_TILE: u32 = TILE_SIDE as u32 - 1;
+/// Les 2²⁶ tuiles d'un axe: au-delà, on revient de l'autre côté du plan
+const TILE_INDEX: u32 = u32::MAX >> TILE_SHIFT;
+
+/// Une tuile de 64x64 maisons, un bit par maison, une ligne par mot
+type Tile = [u64; TILE_SIDE as usize];
+
+/// Hacheur multiplicatif pour les clés de tuiles
+///
+/// Les clés sont des coordonnées de tuiles empaquetées dans un `u64`, pas des
+/// données venant de l'extérieur: la résistance aux collisions provoquées du
+/// hacheur par défaut (SipHash) ne sert à rien et coûte cher.
+#[derive(Default)]
+struct TileHasher(u64);
+
+impl Hasher for TileHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    fn finish(&self) -> u64 {
+        // Les bits hauts du produit sont les mieux mélangés, la table utilise les bas
+        self.0.rotate_left(26)
+    }
+}
+
+/// Ensemble compact de maisons visitées
+///
+/// Le plan est découpé en tuiles de 64x64 maisons. Chaque tuile touchée est un
+/// bitmap de 512 octets, rangé dans une table indexée par la position de la
+/// tuile. Une tournée repasse surtout près des maisons déjà vues: là où un
+/// `HashSet<(i32, i32)>` paie une entrée d'au moins 9 octets par maison, une
+/// tuile occupée à quelques pourcents coûte déjà moins cher.
+///
+/// # Complexité algorithmique
+/// * Temps: O(1) en moyenne par insertion (un accès à la table
+///   puis une opération sur un bit)
+/// * Espace: O(t) où t est le nombre de tuiles touchées, soit 4096 maisons par
+///   tuile au plus
+#[derive(Default)]
+pub struct HouseSet {
+    tiles: HashMap<u64, Box<Tile>, BuildHasherDefault<TileHasher>>,
+    len: usize,
+}
+
+/// Clé d'une tuile à partir de ses indices sur chaque axe
+fn tile_key(tile_x: u32, tile_y: u32) -> u64 {
+    (u64::from(tile_x & TILE_INDEX) << 32) | u64::from(tile_y & TILE_INDEX)
+}
+
+/// Clé de la tuile contenant la maison, et ligne et colonne dans la tuile
+///
+/// Les coordonnées sont lues en complément à deux comme des `u32`: les
+/// négatives tombent dans les dernières tuiles, et décaler une maison revient
+/// à une addition modulo 2³² sur chaque axe.
+fn locate((x, y): House) -> (u64, usize, u32) {
+    let (x, y) = (x as u32, y as u32);
+    let key = tile_key(x >> TILE_SHIFT, y >> TILE_SHIFT);
+    (key, (y & IN_TILE) as usize, x & IN_TILE)
+}
+
+impl HouseSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ajoute une maison, retourne `true` si elle n'y était pas déjà
+    pub fn insert(&mut self, house: House) -> bool {
+        let (key, row, column) = locate(house);
+        self.insert_bits(key, row, 1 << column) == 1
+    }
+
+    /// Ajoute les maisons `bits` d'une ligne de tuile, retourne le nombre de
+    /// maisons nouvelles
+    fn insert_bits(&mut self, key: u64, row: usize, bits: u64) -> u32 {
+        let tile = self
+            .tiles
+            .entry(key)
+            .or_insert_with(|| Box::new([0; TILE_SIDE as usize]));
+        let added = (bits & !tile[row]).count_ones();
+        tile[row] |= bits;
+        self.len += added as usize;
+        added
+    }
+
+    /// Ajoute les maisons de `other` décalées de `offset`, modulo 2³² sur
+    /// chaque axe
+    ///
+    /// Le décalage se fait ligne de tuile par ligne de tuile: une ligne
+    /// décalée de `offset` chevauche au plus deux tuiles voisines.
+    ///
+    /// # Complexité algorithmique
+    /// * Temps: O(t) où t est le nombre de tuiles de `other` (64 lignes chacune),
+    ///   quel que soit le nombre de maisons qu'elles contiennent
+    pub fn merge_shifted(&mut self, other: &HouseSet, (dx, dy): House) {
+        let (dx, dy) = (dx as u32, dy as u32);
+        let (shift_x, shift_y) = (dx & IN_TILE, (dy & IN_TILE) as usize);
+
+        for (&key, tile) in &other.tiles {
+            let tile_x = ((key >> 32) as u32).wrapping_add(dx >> TILE_SHIFT);
+            let tile_y = (key as u32).wrapping_add(dy >> TILE_SHIFT);
+
+            for (row, &bits) in tile.iter().enumerate().filter(|&(_, &bits)| bits != 0) {
+                // La ligne déborde éventuellement sur la tuile du dessus
+                let row = row + shift_y;
+                let tile_y = tile_y.wrapping_add((row >> TILE_SHIFT) as u32);
+                let row = row & IN_TILE as usize;
+
+                // Et ses bits sur la tuile de droite
+                let low = bits << shift_x;
+                if low != 0 {
+                    self.insert_bits(tile_key(tile_x, tile_y), row, low);
+                }
+                if shift_x > 0 && bits >> (TILE_SIDE as u32 - shift_x) != 0 {
+                    self.insert_bits(
+                        tile_key(tile_x.wrapping_add(1), tile_y),
+                        row,
+                        bits >> (TILE_SIDE as u32 - shift_x),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Nombre de maisons de l'ensemble
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Vrai si aucune maison n'a été ajoutée
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xorshift::XorShift;
+    use std::collections::HashSet;
+
+    /// Marche aléatoire déterministe de `len` pas, diagonales comprises
+    fn walk(len: usize) -> impl Iterator<Item = House> {
+        let mut random = XorShift::default();
+        let mut house = (0, 0);
+        (0..len).map(move |_| {
+            let (dx, dy) = random.direction().delta();
+            house = (house.0 + dx, house.1 + dy);
+            house
+        })
+    }
+
+    /// Insertions de part et d'autre des bords de tuiles et aux extrêmes
+    #[test]
+    fn test_insert() {
+        let mut set = HouseSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert((0, 0)));
+        assert!(!set.insert((0, 0)));
+        assert!(set.insert((-1, 0)));
+        assert!(set.insert((0, -1)));
+        assert!(set.insert((63, 64)));
+        assert!(set.insert((64, 64)));
+        assert!(set.insert((i32::MIN, i32::MAX)));
+        assert!(set.insert((i32::MAX, i32::MIN)));
+        assert!(!set.insert((-1, 0)));
+        assert!(!set.insert((i32::MIN, i32::MAX)));
+        assert_eq!(set.len(), 7);
+    }
+
+    /// Même résultat qu'un HashSet sur une longue marche
+    #[test]
+    fn test_matches_hash_set() {
+        let mut set = HouseSet::new();
+        let mut reference = HashSet::new();
+        for house in walk(100_000) {
+            assert_eq!(set.insert(house), reference.insert(house));
+        }
+        assert_eq!(set.len(), reference.len());
+    }
+
+    /// Fusion décalée, y compris à travers les bords de tuiles et du plan
+    #[test]
+    fn test_merge_shifted() {
+        let houses: Vec<House> = walk(20_000).collect();
+        let mut local = HouseSet::new();
+        for &house in &houses {
+            local.insert(house);
+        }
+
+        for offset in [
+            (0, 0),
+            (64, -128),
+            (37, -5),
+            (-1, 63),
+            (i32::MAX, i32::MIN + 7),
+        ] {
+            let mut merged = HouseSet::new();
+            merged.insert((1000, 1000));
+            merged.merge_shifted(&local, offset);
+
+            let mut expected = HouseSet::new();
+            expected.insert((1000, 1000));
+            for &(x, y) in &houses {
+                expected.insert((x.wrapping_add(offset.0), y.wrapping_add(offset.1)));
+            }
+            assert_eq!(merged.len(), expected.len());
+            assert_eq!(merged.tiles, expected.tiles);
+        }
+    }
+}
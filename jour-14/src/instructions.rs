@@ -0,0 +1,580 @@
+use clap::ValueEnum;
+use eyre::Result;
+use std::fmt;
+use std::iter::Peekable;
+use std::slice;
+use std::str::CharIndices;
+
+/// Direction d'un déplacement élémentaire d'une maison
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    /// Déplacement `(dx, dy)` correspondant, y augmentant vers le nord
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::South => (0, -1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::NorthWest => (-1, 1),
+            Direction::SouthEast => (1, -1),
+            Direction::SouthWest => (-1, -1),
+        }
+    }
+}
+
+/// Noeud de l'arbre syntaxique des instructions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// `count` pas dans la même direction (`N`, `N12`, `SW3`...)
+    Step { direction: Direction, count: u32 },
+    /// Bloc répété `count` fois (`(NE)3`)
+    Repeat { body: Vec<Instruction>, count: u32 },
+    /// Suite de pas d'une maison, un octet chacun (syntaxe historique)
+    Path { directions: Vec<Direction> },
+}
+
+impl Instruction {
+    /// Nombre de pas élémentaires, répétitions développées
+    pub fn step_count(&self) -> u64 {
+        match self {
+            Instruction::Step { count, .. } => u64::from(*count),
+            Instruction::Repeat { body, count } => body
+                .iter()
+                .map(Instruction::step_count)
+                .fold(0, u64::saturating_add)
+                .saturating_mul(u64::from(*count)),
+            Instruction::Path { directions } => directions.len() as u64,
+        }
+    }
+}
+
+/// Syntaxe du fichier d'instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Syntax {
+    /// Une lettre N, S, E ou W par pas (format historique), les blancs sont ignorés
+    #[default]
+    Plain,
+    /// Diagonales NE/NW/SE/SW, comptes (`N12`), répétitions (`(NE)3`),
+    /// commentaires `#` et espaces comme séparateurs (`N E` pour un pas nord puis est)
+    Extended,
+}
+
+/// Position d'un caractère dans le texte, ligne et colonne à partir de 1
+///
+/// Les colonnes comptent les caractères, pas les octets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ligne {}, colonne {}", self.line, self.column)
+    }
+}
+
+/// Caractère invalide ignoré avec `skip_invalid`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skipped {
+    pub character: char,
+    pub location: Location,
+}
+
+/// Options d'analyse des instructions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub syntax: Syntax,
+    /// Ignore les caractères invalides au lieu d'échouer (les erreurs de
+    /// structure, parenthèses ou comptes, restent fatales)
+    pub skip_invalid: bool,
+}
+
+/// Instructions analysées et caractères ignorés en chemin
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parsed {
+    pub program: Vec<Instruction>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Marque d'ordre des octets que certains éditeurs placent en tête de fichier
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Pas d'un bloc `Path` en syntaxe historique: assez pour que le bloc coûte
+/// un octet par pas, assez peu pour que le découpage parallèle reste fin
+const PATH_BLOCK: usize = 4096;
+
+/// Analyse les instructions selon la syntaxe choisie, en refusant tout
+/// caractère invalide (raccourci pour les tests)
+///
+/// # Errors
+/// Voir `parse_with`
+#[cfg(test)]
+pub fn parse(text: &str, syntax: Syntax) -> Result<Vec<Instruction>> {
+    let options = ParseOptions {
+        syntax,
+        skip_invalid: false,
+    };
+    Ok(parse_with(text, &options)?.program)
+}
+
+/// Analyse les instructions selon les options
+///
+/// Les blancs (espaces, tabulations, fins de ligne, y compris le saut de
+/// ligne final qu'ajoutent la plupart des éditeurs) et une marque d'ordre
+/// des octets sont ignorés dans les deux syntaxes. Les erreurs donnent la
+/// ligne et la colonne du caractère fautif.
+///
+/// Le texte est lu sur place, caractère par caractère. En syntaxe
+/// historique, les pas sont rangés par blocs `Path` d'un octet par pas.
+///
+/// # Complexité algorithmique
+/// * Temps: O(n) où n est la longueur du texte
+/// * Espace: O(n) pour l'arbre syntaxique, un octet par pas en syntaxe historique
+///
+/// # Errors
+/// Retourne une erreur pour un caractère invalide (sauf avec `skip_invalid`),
+/// une parenthèse non appariée ou un compte nul ou trop grand
+pub fn parse_with(text: &str, options: &ParseOptions) -> Result<Parsed> {
+    let mut parser = Parser {
+        text,
+        chars: text.char_indices().peekable(),
+        location: Location { line: 1, column: 1 },
+        skip_invalid: options.skip_invalid,
+        skipped: Vec::new(),
+    };
+    let program = match options.syntax {
+        Syntax::Plain => parser.parse_plain()?,
+        Syntax::Extended => parser.parse_extended()?,
+    };
+    Ok(Parsed {
+        program,
+        skipped: parser.skipped,
+    })
+}
+
+/// État de l'analyse d'un texte
+struct Parser<'a> {
+    text: &'a str,
+    /// Caractères restants, avec leur décalage en octets
+    chars: Peekable<CharIndices<'a>>,
+    /// Ligne et colonne du prochain caractère
+    location: Location,
+    skip_invalid: bool,
+    skipped: Vec<Skipped>,
+}
+
+impl Parser<'_> {
+    /// Prochain caractère, sans avancer
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    /// Décalage en octets du prochain caractère
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map_or(self.text.len(), |&(offset, _)| offset)
+    }
+
+    /// Avance d'un caractère en tenant la ligne et la colonne à jour
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.location = Location {
+                line: self.location.line + 1,
+                column: 1,
+            };
+        } else {
+            self.location.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Ignore le caractère invalide suivant, ou échoue
+    fn invalid(&mut self) -> Result<()> {
+        let location = self.location;
+        let Some(character) = self.bump() else {
+            return Ok(());
+        };
+        if !self.skip_invalid {
+            eyre::bail!(
+                "Caractère invalide '{}' dans les instructions ({})",
+                character.escape_debug(),
+                location
+            );
+        }
+        self.skipped.push(Skipped {
+            character,
+            location,
+        });
+        Ok(())
+    }
+
+    /// Format historique: chaque lettre est un pas
+    fn parse_plain(&mut self) -> Result<Vec<Instruction>> {
+        let mut program = Vec::new();
+        let mut directions = Vec::with_capacity(PATH_BLOCK);
+        while let Some(c) = self.peek() {
+            let direction = match c {
+                'N' => Direction::North,
+                'S' => Direction::South,
+                'E' => Direction::East,
+                'W' => Direction::West,
+                c if c.is_whitespace() || c == BYTE_ORDER_MARK => {
+                    self.bump();
+                    continue;
+                }
+                _ => {
+                    self.invalid()?;
+                    continue;
+                }
+            };
+            self.bump();
+            directions.push(direction);
+            if directions.len() == PATH_BLOCK {
+                let full = std::mem::replace(&mut directions, Vec::with_capacity(PATH_BLOCK));
+                program.push(Instruction::Path { directions: full });
+            }
+        }
+        if !directions.is_empty() {
+            directions.shrink_to_fit();
+            program.push(Instruction::Path { directions });
+        }
+        Ok(program)
+    }
+
+    /// Syntaxe étendue, analysée avec une pile de blocs plutôt que par récursion
+    /// pour supporter des imbrications arbitrairement profondes
+    fn parse_extended(&mut self) -> Result<Vec<Instruction>> {
+        // Blocs en cours de construction, avec la position de leur parenthèse ouvrante
+        let mut blocks: Vec<(Location, Vec<Instruction>)> = Vec::new();
+        let mut current: Vec<Instruction> = Vec::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                // Commentaire jusqu'à la fin de la ligne
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                c if c.is_whitespace() || c == BYTE_ORDER_MARK => {
+                    self.bump();
+                }
+                '(' => {
+                    blocks.push((self.location, std::mem::take(&mut current)));
+                    self.bump();
+                }
+                ')' => {
+                    let Some((_, parent)) = blocks.pop() else {
+                        eyre::bail!(
+                            "Parenthèse fermante sans parenthèse ouvrante ({})",
+                            self.location
+                        );
+                    };
+                    self.bump();
+                    let count = self.parse_count()?;
+                    let body = std::mem::replace(&mut current, parent);
+                    current.push(Instruction::Repeat { body, count });
+                }
+                'N' | 'S' | 'E' | 'W' => {
+                    self.bump();
+                    let diagonal = match (c, self.peek()) {
+                        ('N', Some('E')) => Some(Direction::NorthEast),
+                        ('N', Some('W')) => Some(Direction::NorthWest),
+                        ('S', Some('E')) => Some(Direction::SouthEast),
+                        ('S', Some('W')) => Some(Direction::SouthWest),
+                        _ => None,
+                    };
+                    let direction = match (diagonal, c) {
+                        (Some(diagonal), _) => {
+                            self.bump();
+                            diagonal
+                        }
+                        (None, 'N') => Direction::North,
+                        (None, 'S') => Direction::South,
+                        (None, 'E') => Direction::East,
+                        (None, _) => Direction::West,
+                    };
+                    let count = self.parse_count()?;
+                    current.push(Instruction::Step { direction, count });
+                }
+                _ => self.invalid()?,
+            }
+        }
+
+        if let Some(&(open, _)) = blocks.last() {
+            eyre::bail!("Parenthèse ouvrante jamais fermée ({})", open);
+        }
+
+        Ok(current)
+    }
+
+    /// Lit le compte optionnel qui suit un pas ou un bloc (1 par défaut)
+    fn parse_count(&mut self) -> Result<u32> {
+        let (location, start) = (self.location, self.offset());
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let digits = &self.text[start..self.offset()];
+        if digits.is_empty() {
+            return Ok(1);
+        }
+
+        match digits.parse::<u32>() {
+            Ok(0) => eyre::bail!("Compte nul ({})", location),
+            Ok(count) => Ok(count),
+            Err(_) => eyre::bail!("Compte trop grand '{}' ({})", digits, location),
+        }
+    }
+}
+
+/// Parcourt les pas élémentaires du programme, répétitions développées à la volée
+///
+/// Les répétitions ne sont jamais recopiées en mémoire: `(NE)1000000` ne
+/// coûte que la pile d'exécution.
+pub fn steps(program: &[Instruction]) -> Steps<'_> {
+    Steps {
+        frames: vec![Frame {
+            body: program,
+            index: 0,
+            remaining: 1,
+        }],
+        pending: None,
+        path: [].iter(),
+    }
+}
+
+/// Bloc en cours d'exécution: prochaine instruction et répétitions restantes
+struct Frame<'a> {
+    body: &'a [Instruction],
+    index: usize,
+    remaining: u32,
+}
+
+/// Itérateur sur les pas élémentaires, voir `steps`
+pub struct Steps<'a> {
+    frames: Vec<Frame<'a>>,
+    /// Pas restants de l'instruction `Step` en cours
+    pending: Option<(Direction, u32)>,
+    /// Pas restants de l'instruction `Path` en cours
+    path: slice::Iter<'a, Direction>,
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Direction;
+
+    fn next(&mut self) -> Option<Direction> {
+        loop {
+            if let Some((direction, left)) = &mut self.pending {
+                let direction = *direction;
+                *left -= 1;
+                if *left == 0 {
+                    self.pending = None;
+                }
+                return Some(direction);
+            }
+            if let Some(&direction) = self.path.next() {
+                return Some(direction);
+            }
+
+            let frame = self.frames.last_mut()?;
+            if frame.index == frame.body.len() {
+                // Fin du bloc: on le recommence ou on remonte au bloc parent
+                frame.remaining -= 1;
+                if frame.remaining == 0 {
+                    self.frames.pop();
+                } else {
+                    frame.index = 0;
+                }
+                continue;
+            }
+
+            let instruction = &frame.body[frame.index];
+            frame.index += 1;
+            match instruction {
+                Instruction::Step { direction, count } => {
+                    self.pending = Some((*direction, *count));
+                }
+                Instruction::Path { directions } => self.path = directions.iter(),
+                Instruction::Repeat { body, count } => {
+                    // Un groupe sans aucun pas, même imbriqué comme `((()))`,
+                    // tournerait à vide `count` fois: on le saute
+                    if instruction.step_count() > 0 {
+                        self.frames.push(Frame {
+                            body,
+                            index: 0,
+                            remaining: *count,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    fn expand(text: &str, syntax: Syntax) -> Result<Vec<Direction>> {
+        Ok(steps(&parse(text, syntax)?).collect())
+    }
+
+    /// En syntaxe historique, NE reste un pas nord puis un pas est
+    #[test]
+    fn test_plain() {
+        assert_eq!(
+            expand("NESW", Syntax::Plain).unwrap(),
+            [North, East, South, West]
+        );
+        assert!(expand("N(E", Syntax::Plain).is_err());
+
+        // Les pas sont rangés par blocs d'un octet par pas
+        let program = parse(&"NE".repeat(PATH_BLOCK), Syntax::Plain).unwrap();
+        let counts: Vec<u64> = program.iter().map(Instruction::step_count).collect();
+        assert_eq!(counts, [PATH_BLOCK as u64, PATH_BLOCK as u64]);
+        assert_eq!(steps(&program).nth(PATH_BLOCK + 1), Some(East));
+        assert_eq!(parse(" \n", Syntax::Plain).unwrap(), []);
+    }
+
+    /// Les instructions à une lettre restent valides en syntaxe étendue
+    #[test]
+    fn test_extended_single_letters() {
+        assert_eq!(
+            expand("NNSS", Syntax::Extended).unwrap(),
+            [North, North, South, South]
+        );
+        assert_eq!(expand("N E", Syntax::Extended).unwrap(), [North, East]);
+    }
+
+    /// Diagonales, comptes et répétitions imbriquées
+    #[test]
+    fn test_extended() {
+        assert_eq!(
+            expand("NE SW2 W", Syntax::Extended).unwrap(),
+            [NorthEast, SouthWest, SouthWest, West]
+        );
+        assert_eq!(
+            expand("(N E2)2", Syntax::Extended).unwrap(),
+            [North, East, East, North, East, East]
+        );
+        assert_eq!(
+            expand("((NW)2 S)2", Syntax::Extended).unwrap(),
+            [NorthWest, NorthWest, South, NorthWest, NorthWest, South]
+        );
+        assert_eq!(
+            parse("(NE)3", Syntax::Extended).unwrap(),
+            [Instruction::Repeat {
+                body: vec![Instruction::Step {
+                    direction: NorthEast,
+                    count: 1
+                }],
+                count: 3
+            }]
+        );
+        assert!(expand("()5 N", Syntax::Extended).unwrap() == [North]);
+    }
+
+    /// Les commentaires s'arrêtent à la fin de la ligne
+    #[test]
+    fn test_comments() {
+        let text = "# tournée du matin\nN2 # vers le nord\n# fin\nE";
+        assert_eq!(
+            expand(text, Syntax::Extended).unwrap(),
+            [North, North, East]
+        );
+    }
+
+    /// Les erreurs donnent la ligne et la colonne
+    #[test]
+    fn test_errors() {
+        let error = |text| parse(text, Syntax::Extended).unwrap_err().to_string();
+        assert!(error("NNXS").contains("'X' dans les instructions (ligne 1, colonne 3)"));
+        assert!(error("N\n (E").contains("jamais fermée (ligne 2, colonne 2)"));
+        assert!(error("N E)2").contains("ligne 1, colonne 4"));
+        assert!(error("N0").contains("Compte nul (ligne 1, colonne 2)"));
+        assert!(error("N99999999999").contains("trop grand"));
+
+        let error = |text| parse(text, Syntax::Plain).unwrap_err().to_string();
+        assert!(error("NNSS\nNE\nS-N").contains("'-' dans les instructions (ligne 3, colonne 2)"));
+    }
+
+    /// Les blancs, le saut de ligne final et la marque d'ordre des octets sont tolérés
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            expand("\u{feff}NN\r\nS\tE \n", Syntax::Plain).unwrap(),
+            [North, North, South, East]
+        );
+        assert_eq!(expand("NE\n", Syntax::Extended).unwrap(), [NorthEast]);
+    }
+
+    /// Avec skip_invalid, les caractères invalides sont listés et ignorés
+    #[test]
+    fn test_skip_invalid() {
+        let options = ParseOptions {
+            syntax: Syntax::Plain,
+            skip_invalid: true,
+        };
+        let parsed = parse_with("NxS\nE?", &options).unwrap();
+        assert_eq!(
+            steps(&parsed.program).collect::<Vec<_>>(),
+            [North, South, East]
+        );
+        assert_eq!(
+            parsed.skipped,
+            vec![
+                Skipped {
+                    character: 'x',
+                    location: Location { line: 1, column: 2 }
+                },
+                Skipped {
+                    character: '?',
+                    location: Location { line: 2, column: 2 }
+                },
+            ]
+        );
+
+        // Les colonnes comptent les caractères, pas les octets
+        let parsed = parse_with("éN?", &options).unwrap();
+        assert_eq!(parsed.skipped[1].location, Location { line: 1, column: 3 });
+
+        // Les erreurs de structure restent fatales
+        let options = ParseOptions {
+            syntax: Syntax::Extended,
+            skip_invalid: true,
+        };
+        assert_eq!(parse_with("N;E2", &options).unwrap().skipped.len(), 1);
+        assert!(parse_with("N;E2)", &options).is_err());
+    }
+
+    /// Les grandes répétitions sont développées paresseusement
+    #[test]
+    fn test_large_repeat() {
+        let program = parse("(NE)4000000000", Syntax::Extended).unwrap();
+        assert_eq!(steps(&program).take(3).count(), 3);
+    }
+
+    /// Les répétitions de groupes vides sont sautées, même imbriquées
+    #[test]
+    fn test_empty_repeat() {
+        let program = parse("((()))4000000000 N", Syntax::Extended).unwrap();
+        assert_eq!(steps(&program).collect::<Vec<_>>(), vec![North]);
+    }
+}
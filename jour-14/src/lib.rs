@@ -0,0 +1,141 @@
+//! Tournée des livreurs derrière l'outil en ligne de commande `jour-14`
+//!
+//! Les modules sont dans une bibliothèque pour que les benchmarks de
+//! `benches/` puissent s'en servir.
+
+use eyre::Result;
+
+pub mod cli;
+pub mod heatmap;
+pub mod houses;
+pub mod instructions;
+pub mod obstacles;
+pub mod parallel;
+pub mod replay;
+pub mod team;
+pub mod visits;
+pub mod xorshift;
+
+use crate::houses::HouseSet;
+use crate::instructions::Instruction;
+use crate::obstacles::{Move, Obstacles, Walker};
+
+/// Compte le nombre de maisons uniques visitées en suivant une série d'instructions de déplacement.
+///
+/// # Arguments
+/// * `program` - Les instructions analysées par `instructions::parse_with`
+/// * `obstacles` - Les maisons bloquées et la conduite à tenir devant elles
+///
+/// # Returns
+/// Le nombre de maisons uniques visitées (position de départ incluse)
+///
+/// # Complexité algorithmique
+/// * Temps: O(n) où n est le nombre de pas élémentaires
+/// * Espace: O(t) où t est le nombre de tuiles de 64x64 maisons touchées,
+///   voir `houses::HouseSet`; les nombres de passages ne sont pas conservés
+///
+/// # Errors
+/// Retourne une erreur si un pas mène sur un obstacle avec la politique `Fail`,
+/// ou si le livreur sort des coordonnées représentables par un `i32`
+pub fn count_unique_houses(program: &[Instruction], obstacles: &Obstacles) -> Result<usize> {
+    let mut visited = HouseSet::new();
+    let mut walker = Walker::new(obstacles);
+    visited.insert(walker.position);
+
+    for direction in instructions::steps(program) {
+        match walker.walk(direction)? {
+            Move::Moved(house) => {
+                visited.insert(house);
+            }
+            Move::Skipped => {}
+            Move::Stopped => break,
+        }
+    }
+
+    Ok(visited.len())
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::Syntax;
+
+    /// Analyse les instructions au format historique puis compte les maisons
+    fn count(text: &str) -> Result<usize> {
+        count_unique_houses(
+            &instructions::parse(text, Syntax::Plain)?,
+            &Obstacles::default(),
+        )
+    }
+
+    /// Test avec une chaîne vide
+    /// Vérifie que la position de départ compte comme une maison visitée
+    #[test]
+    fn test_empty() {
+        assert_eq!(count("").unwrap(), 1);
+    }
+
+    /// Test avec un seul déplacement
+    /// Vérifie que 2 maisons sont visitées (départ + arrivée)
+    #[test]
+    fn test_single_move() {
+        assert_eq!(count("N").unwrap(), 2);
+    }
+
+    /// Test avec une séquence de déplacements sans retour
+    /// NNESESW devrait visiter 8 maisons uniques
+    #[test]
+    fn test_example_1() {
+        assert_eq!(count("NNESESW").unwrap(), 8);
+    }
+
+    /// Test avec des déplacements qui revisitent des positions
+    /// NNSS: (0,0) -> (0,1) -> (0,2) -> (0,1) -> (0,0)
+    /// Devrait compter 3 maisons uniques: (0,0), (0,1), (0,2)
+    #[test]
+    fn test_example_2() {
+        assert_eq!(count("NNSS").unwrap(), 3);
+    }
+
+    /// Test avec un caractère invalide
+    /// Vérifie que la fonction retourne une erreur pour un caractère non reconnu
+    #[test]
+    fn test_invalid_character() {
+        assert!(count("NXS").is_err());
+    }
+
+    /// Test avec la syntaxe étendue
+    /// (NE)2 S2: (0,0) -> (1,1) -> (2,2) -> (2,1) -> (2,0)
+    #[test]
+    fn test_extended_syntax() {
+        let program = instructions::parse("(NE)2 S2 # retour", Syntax::Extended).unwrap();
+        assert_eq!(
+            count_unique_houses(&program, &Obstacles::default()).unwrap(),
+            5
+        );
+    }
+
+    /// Test avec le saut de ligne final qu'ajoutent la plupart des éditeurs
+    #[test]
+    fn test_trailing_newline() {
+        assert_eq!(count("NNSS\n").unwrap(), 3);
+        assert_eq!(count("NN\r\nSS\r\n").unwrap(), 3);
+    }
+
+    /// Même résultat que le relevé complet des passages, obstacles compris
+    #[test]
+    fn test_matches_visits() {
+        let program = instructions::parse("(N2 E SW)40 (W3 NE)25", Syntax::Extended).unwrap();
+        let obstacles = Obstacles {
+            map: obstacles::ObstacleMap::parse("..#\n.H.\n#..\n").unwrap(),
+            policy: obstacles::BlockedPolicy::Skip,
+        };
+        assert_eq!(
+            count_unique_houses(&program, &obstacles).unwrap(),
+            visits::record(&program, &obstacles)
+                .unwrap()
+                .unique_houses()
+        );
+    }
+}
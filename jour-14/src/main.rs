@@ -1,122 +1,230 @@
+use clap::Parser;
 use eyre::{Result, WrapErr};
-use std::collections::HashSet;
 use std::fs;
+use std::thread;
 
-/// Compte le nombre de maisons uniques visitées en suivant une série d'instructions de déplacement.
-///
-/// # Arguments
-/// * `instructions` - Une chaîne de caractères contenant les directions (N, S, E, W)
-///
-/// # Returns
-/// Le nombre de maisons uniques visitées (position de départ incluse)
-///
-/// # Complexité algorithmique
-/// * Temps: O(n) où n est la longueur des instructions
-///   - On parcourt chaque caractère une fois
-///   - Les opérations d'insertion dans le HashSet sont O(1) en moyenne
-/// * Espace: O(n) dans le pire cas
-///   - Le HashSet peut contenir jusqu'à n+1 positions uniques si tous les déplacements
-///     mènent à de nouvelles maisons
-///
-/// # Errors
-/// Retourne une erreur si un caractère invalide est rencontré dans les instructions
-fn count_unique_houses(instructions: &str) -> Result<usize> {
-    // HashSet pour stocker toutes les positions visitées
-    // Utilise un tuple (x, y) comme clé pour représenter chaque position
-    let mut visited = HashSet::new();
-
-    // Position initiale du livreur
-    let mut x = 0;
-    let mut y = 0;
-
-    // Enregistre la position de départ
-    visited.insert((x, y));
-
-    // Parcourt chaque instruction de déplacement
-    // Complexité: O(n) où n = nombre d'instructions
-    for c in instructions.chars() {
-        // Met à jour la position selon la direction
-        match c {
-            'N' => y += 1, // Nord: augmente y
-            'S' => y -= 1, // Sud: diminue y
-            'E' => x += 1, // Est: augmente x
-            'W' => x -= 1, // Ouest: diminue x
-            _ => eyre::bail!(
-                "Caractère invalide '{}' dans les instructions (position: {}, {})",
-                c,
-                x,
-                y
-            ),
-        }
+use jour_14::cli::{self, Commands};
+use jour_14::obstacles::Blocked;
+use jour_14::{count_unique_houses, heatmap, instructions, parallel, replay, team, visits};
 
-        // Ajoute la nouvelle position au set (ignore si déjà visitée)
-        // Complexité de l'insertion: O(1) en moyenne
-        visited.insert((x, y));
+/// Affiche les pas bloqués par des obstacles
+fn print_blocked(blocked: &[Blocked], stopped: bool) {
+    println!("Déplacements bloqués : {}", blocked.len());
+    for b in blocked {
+        println!(
+            "  pas {} : ({}, {}) bloquée depuis ({}, {})",
+            b.step, b.to.0, b.to.1, b.from.0, b.from.1
+        );
+    }
+    if let (true, Some(last)) = (stopped, blocked.last()) {
+        println!("Tournée arrêtée au pas {}", last.step);
     }
+}
 
-    // Retourne le nombre de positions uniques visitées
-    // Complexité: O(1)
-    Ok(visited.len())
+/// Affiche les statistiques de fréquentation d'une tournée
+fn print_stats(visits: &visits::Visits, top: usize) {
+    println!(
+        "Nombre de maisons uniques visitées : {}",
+        visits.unique_houses()
+    );
+
+    println!("Maisons les plus visitées :");
+    for ((x, y), count) in visits.most_visited(top) {
+        println!("  ({}, {}) : {} passages", x, y, count);
+    }
+
+    println!("Maisons visitées exactement k fois :");
+    for (k, houses) in visits.histogram() {
+        println!("  k = {} : {} maisons", k, houses);
+    }
+
+    match visits.first_revisit {
+        Some(revisit) => println!(
+            "Première maison visitée deux fois : ({}, {}) au pas {}",
+            revisit.house.0, revisit.house.1, revisit.step
+        ),
+        None => println!("Aucune maison visitée deux fois"),
+    }
+
+    let ((x, y), manhattan) = visits.furthest_manhattan();
+    println!(
+        "Maison la plus éloignée (Manhattan) : ({}, {}) à {}",
+        x, y, manhattan
+    );
+    let ((x, y), euclidean) = visits.furthest_euclidean();
+    println!(
+        "Maison la plus éloignée (vol d'oiseau) : ({}, {}) à {:.2}",
+        x, y, euclidean
+    );
 }
 
 /// Point d'entrée principal du programme
 ///
-/// Lit les instructions depuis le fichier "steps" et calcule le nombre de maisons uniques visitées.
+/// Lit les instructions depuis le fichier donné en ligne de commande ("steps" par défaut)
+/// et calcule le nombre de maisons uniques visitées par l'équipe de livreurs.
 ///
 /// # Complexité globale
-/// * Temps: O(n) où n est la taille du fichier
+/// * Temps: O(n) où n est la taille du fichier, plus O(K² · m) pour les recouvrements
+///   entre les K livreurs
 ///
 /// # Errors
 /// Retourne une erreur si le fichier ne peut pas être lu ou si les instructions sont invalides
 fn main() -> Result<()> {
-    let instructions =
-        fs::read_to_string("steps").wrap_err("Impossible de lire le fichier 'steps'")?;
+    let cli = cli::Cli::parse();
 
-    let result = count_unique_houses(&instructions)
-        .wrap_err("Erreur lors du traitement des instructions")?;
+    // Les options du comptage n'ont pas de sens pour la carte ni la relecture
+    if matches!(
+        cli.command,
+        Some(Commands::Heatmap { .. } | Commands::Replay { .. })
+    ) && (cli.stats || cli.parallel.is_some())
+    {
+        eyre::bail!("--stats et --parallel ne s'appliquent qu'au comptage");
+    }
 
-    println!("Nombre de maisons uniques visitées : {}", result);
-    Ok(())
-}
+    let instructions = fs::read_to_string(&cli.input)
+        .wrap_err_with(|| format!("Impossible de lire le fichier '{}'", cli.input))?;
 
-/// Module de tests unitaires
-#[cfg(test)]
-mod tests {
-    use super::*;
+    let parsed = instructions::parse_with(&instructions, &cli.parse_options())
+        .wrap_err_with(|| format!("Erreur lors de la lecture de '{}'", cli.input))?;
+    let program = parsed.program;
 
-    /// Test avec une chaîne vide
-    /// Vérifie que la position de départ compte comme une maison visitée
-    #[test]
-    fn test_empty() {
-        assert_eq!(count_unique_houses("").unwrap(), 1);
+    // Les caractères ignorés avec --skip-invalid méritent tout de même un avertissement
+    if !parsed.skipped.is_empty() {
+        eprintln!(
+            "Attention : {} caractères invalides ignorés",
+            parsed.skipped.len()
+        );
+        for skipped in &parsed.skipped {
+            eprintln!(
+                "  {} : '{}'",
+                skipped.location,
+                skipped.character.escape_debug()
+            );
+        }
     }
+    let obstacles = cli.obstacles()?;
 
-    /// Test avec un seul déplacement
-    /// Vérifie que 2 maisons sont visitées (départ + arrivée)
-    #[test]
-    fn test_single_move() {
-        assert_eq!(count_unique_houses("N").unwrap(), 2);
+    if let Some(Commands::Heatmap {
+        output,
+        scale,
+        columns,
+        rows,
+        no_color,
+    }) = &cli.command
+    {
+        if cli.couriers != 1 {
+            eyre::bail!("La carte des passages n'est disponible qu'avec un seul livreur");
+        }
+        let visits = visits::record(&program, &obstacles)
+            .wrap_err("Erreur lors du traitement des instructions")?;
+        match output {
+            Some(output) => {
+                heatmap::write_image(&visits, output, *scale)?;
+                println!("Carte des passages écrite dans {}", output);
+            }
+            None => {
+                // Terminal classique 80x24 si la taille est inconnue (redirection)
+                let (term_columns, term_rows) = crossterm::terminal::size().unwrap_or((80, 24));
+                let columns = columns.unwrap_or(usize::from(term_columns));
+                // Garde la place de la légende et de l'invite du shell
+                let rows = rows.unwrap_or(usize::from(term_rows).saturating_sub(2));
+                print!(
+                    "{}",
+                    heatmap::render_terminal(&visits, columns, rows, !no_color)
+                );
+            }
+        }
+        return Ok(());
     }
 
-    /// Test avec une séquence de déplacements sans retour
-    /// NNESESW devrait visiter 8 maisons uniques
-    #[test]
-    fn test_example_1() {
-        assert_eq!(count_unique_houses("NNESESW").unwrap(), 8);
+    if let Some(Commands::Replay {
+        speed,
+        paused,
+        no_color,
+    }) = &cli.command
+    {
+        if cli.couriers != 1 {
+            eyre::bail!("La relecture n'est disponible qu'avec un seul livreur");
+        }
+        if !(speed.is_finite() && *speed > 0.0) {
+            eyre::bail!("La vitesse doit être un nombre de pas par seconde positif");
+        }
+        let options = replay::ReplayOptions {
+            speed: *speed,
+            paused: *paused,
+            color: !no_color,
+        };
+        return replay::run(&program, &obstacles, &options);
     }
 
-    /// Test avec des déplacements qui revisitent des positions
-    /// NNSS: (0,0) -> (0,1) -> (0,2) -> (0,1) -> (0,0)
-    /// Devrait compter 3 maisons uniques: (0,0), (0,1), (0,2)
-    #[test]
-    fn test_example_2() {
-        assert_eq!(count_unique_houses("NNSS").unwrap(), 3);
+    // Comptage par morceaux en parallèle, même résultat que le comptage historique
+    if let Some(chunks) = cli.parallel {
+        if cli.couriers != 1 || cli.stats || cli.map.is_some() {
+            eyre::bail!(
+                "--parallel n'est disponible qu'avec un seul livreur, sans --stats ni --map"
+            );
+        }
+        let chunks =
+            chunks.unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from));
+        let result = parallel::count_unique_houses(&program, chunks)
+            .wrap_err("Erreur lors du traitement des instructions")?;
+        println!("Nombre de maisons uniques visitées : {}", result);
+        return Ok(());
     }
 
-    /// Test avec un caractère invalide
-    /// Vérifie que la fonction retourne une erreur pour un caractère non reconnu
-    #[test]
-    fn test_invalid_character() {
-        assert!(count_unique_houses("NXS").is_err());
+    // Un seul livreur: le comptage historique suffit
+    if cli.couriers == 1 {
+        if !cli.stats && cli.map.is_none() {
+            let result = count_unique_houses(&program, &obstacles)
+                .wrap_err("Erreur lors du traitement des instructions")?;
+            println!("Nombre de maisons uniques visitées : {}", result);
+            return Ok(());
+        }
+
+        let visits = visits::record(&program, &obstacles)
+            .wrap_err("Erreur lors du traitement des instructions")?;
+        if cli.stats {
+            print_stats(&visits, cli.top);
+        } else {
+            println!(
+                "Nombre de maisons uniques visitées : {}",
+                visits.unique_houses()
+            );
+        }
+        if cli.map.is_some() {
+            print_blocked(&visits.blocked, visits.stopped);
+        }
+        return Ok(());
     }
+    if cli.stats {
+        eyre::bail!("--stats n'est disponible qu'avec un seul livreur");
+    }
+
+    let report = team::deliver(&program, cli.couriers, &obstacles)
+        .wrap_err("Erreur lors du traitement des instructions")?;
+
+    println!(
+        "Nombre de maisons uniques visitées : {}",
+        report.unique_houses
+    );
+    for (courier, houses) in report.per_courier.iter().enumerate() {
+        println!("  livreur {} : {} maisons", courier + 1, houses);
+    }
+    println!(
+        "Maisons visitées par plusieurs livreurs : {}",
+        report.shared_houses
+    );
+    for (i, j, common) in &report.pairwise_overlap {
+        println!(
+            "  livreurs {} et {} : {} maisons en commun",
+            i + 1,
+            j + 1,
+            common
+        );
+    }
+    if cli.map.is_some() {
+        println!("Déplacements bloqués : {}", report.blocked);
+    }
+
+    Ok(())
 }
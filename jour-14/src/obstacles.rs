@@ -0,0 +1,297 @@
+use clap::ValueEnum;
+use eyre::{Result, WrapErr};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::instructions::Direction;
+use crate::visits::House;
+
+/// Conduite à tenir quand un pas mène sur une maison bloquée
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BlockedPolicy {
+    /// Échoue en donnant le numéro du pas
+    #[default]
+    Fail,
+    /// Ignore le pas, le livreur reste sur place
+    Skip,
+    /// Arrête la tournée à ce pas
+    Stop,
+}
+
+/// Carte des maisons bloquées du village
+///
+/// Format du fichier: une ligne par rangée de maisons, le nord en haut.
+/// `#` bloque une maison, `.` ou une espace la laisse libre et `H` marque la
+/// maison de départ `(0, 0)`; sans `H`, le départ est le coin en bas à
+/// gauche. Les maisons hors de la carte sont libres.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObstacleMap {
+    blocked: HashSet<House>,
+}
+
+impl ObstacleMap {
+    /// Lit une carte depuis un fichier
+    pub fn load(path: &str) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Impossible de lire la carte '{}'", path))?;
+        Self::parse(&text).wrap_err_with(|| format!("Carte '{}' invalide", path))
+    }
+
+    /// Analyse le texte d'une carte
+    ///
+    /// # Errors
+    /// Retourne une erreur pour un caractère inconnu ou plusieurs `H`, avec
+    /// la ligne et la colonne (à partir de 1), ou si le départ est bloqué
+    pub fn parse(text: &str) -> Result<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut home = None;
+        let mut cells = Vec::new();
+
+        for (row, line) in lines.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                match c {
+                    '#' => cells.push((column, row)),
+                    '.' | ' ' => {}
+                    'H' if home.is_some() => eyre::bail!(
+                        "Plusieurs maisons de départ 'H' (ligne {}, colonne {})",
+                        row + 1,
+                        column + 1
+                    ),
+                    'H' => home = Some((column, row)),
+                    _ => eyre::bail!(
+                        "Caractère inconnu '{}' dans la carte (ligne {}, colonne {})",
+                        c.escape_debug(),
+                        row + 1,
+                        column + 1
+                    ),
+                }
+            }
+        }
+
+        // Coordonnées relatives au départ, y augmentant vers le nord
+        let (home_column, home_row) = home.unwrap_or((0, lines.len().saturating_sub(1)));
+        let blocked: HashSet<House> = cells
+            .into_iter()
+            .map(|(column, row)| {
+                (
+                    column as i32 - home_column as i32,
+                    home_row as i32 - row as i32,
+                )
+            })
+            .collect();
+
+        if blocked.contains(&(0, 0)) {
+            eyre::bail!("La maison de départ est bloquée");
+        }
+
+        Ok(ObstacleMap { blocked })
+    }
+
+    pub fn is_blocked(&self, house: House) -> bool {
+        self.blocked.contains(&house)
+    }
+}
+
+/// Carte des obstacles et conduite à tenir devant une maison bloquée
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Obstacles {
+    pub map: ObstacleMap,
+    pub policy: BlockedPolicy,
+}
+
+/// Pas qui n'a pas pu être fait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blocked {
+    /// Numéro du pas élémentaire, à partir de 1
+    pub step: u64,
+    /// Maison où se trouvait le livreur
+    pub from: House,
+    /// Maison bloquée visée par le pas
+    pub to: House,
+}
+
+/// Ce qu'a donné un pas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    /// Le livreur est arrivé dans cette maison
+    Moved(House),
+    /// Le pas a été ignoré, le livreur n'a pas bougé
+    Skipped,
+    /// La tournée s'arrête, plus aucun pas ne sera fait
+    Stopped,
+}
+
+/// Livreur qui avance pas à pas en respectant les obstacles
+pub struct Walker<'a> {
+    obstacles: &'a Obstacles,
+    /// Position actuelle
+    pub position: House,
+    /// Nombre de pas lus, y compris les pas bloqués
+    pub step: u64,
+    /// Pas bloqués, dans l'ordre
+    pub blocked: Vec<Blocked>,
+    /// Le livreur s'est arrêté devant un obstacle
+    pub stopped: bool,
+}
+
+impl<'a> Walker<'a> {
+    /// Livreur à la maison de départ
+    pub fn new(obstacles: &'a Obstacles) -> Self {
+        Walker {
+            obstacles,
+            position: (0, 0),
+            step: 0,
+            blocked: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    /// Fait un pas dans `direction`
+    ///
+    /// # Errors
+    /// Retourne une erreur si la maison visée est bloquée avec la politique
+    /// `Fail`, ou si elle sort des coordonnées représentables par un `i32`
+    pub fn walk(&mut self, direction: Direction) -> Result<Move> {
+        if self.stopped {
+            return Ok(Move::Stopped);
+        }
+        self.step += 1;
+
+        // Au-delà de ±2³¹ maisons, les coordonnées ne tiennent plus dans un i32
+        let (dx, dy) = direction.delta();
+        let target = match (
+            self.position.0.checked_add(dx),
+            self.position.1.checked_add(dy),
+        ) {
+            (Some(x), Some(y)) => (x, y),
+            _ => eyre::bail!(
+                "Pas {}: le livreur sort du plan des coordonnées représentables (livreur en ({}, {}))",
+                self.step,
+                self.position.0,
+                self.position.1
+            ),
+        };
+        if !self.obstacles.map.is_blocked(target) {
+            self.position = target;
+            return Ok(Move::Moved(target));
+        }
+
+        let blocked = Blocked {
+            step: self.step,
+            from: self.position,
+            to: target,
+        };
+        match self.obstacles.policy {
+            BlockedPolicy::Fail => eyre::bail!(
+                "Pas {} bloqué: la maison ({}, {}) est un obstacle (livreur en ({}, {}))",
+                blocked.step,
+                target.0,
+                target.1,
+                blocked.from.0,
+                blocked.from.1
+            ),
+            BlockedPolicy::Skip => {
+                self.blocked.push(blocked);
+                Ok(Move::Skipped)
+            }
+            BlockedPolicy::Stop => {
+                self.blocked.push(blocked);
+                self.stopped = true;
+                Ok(Move::Stopped)
+            }
+        }
+    }
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    /// Carte 3x3 avec le départ au centre et un mur à l'est
+    const MAP: &str = "..#\n.H#\n...\n";
+
+    fn obstacles(policy: BlockedPolicy) -> Obstacles {
+        Obstacles {
+            map: ObstacleMap::parse(MAP).unwrap(),
+            policy,
+        }
+    }
+
+    /// Les coordonnées sont relatives à `H`, le nord en haut
+    #[test]
+    fn test_parse_map() {
+        let map = ObstacleMap::parse(MAP).unwrap();
+        assert_eq!(map.blocked.len(), 2);
+        assert!(map.is_blocked((1, 0)));
+        assert!(map.is_blocked((1, 1)));
+        assert!(!map.is_blocked((1, -1)));
+
+        // Sans H, le départ est en bas à gauche
+        let map = ObstacleMap::parse("#.\n..\n").unwrap();
+        assert!(map.is_blocked((0, 1)));
+    }
+
+    /// Erreurs de carte avec ligne et colonne
+    #[test]
+    fn test_parse_map_errors() {
+        let error = |text| ObstacleMap::parse(text).unwrap_err().to_string();
+        assert!(error("..\n.x\n").contains("'x' dans la carte (ligne 2, colonne 2)"));
+        assert!(error("H.\n.H\n").contains("ligne 2, colonne 2"));
+        assert!(error("..\n#.\n").contains("départ est bloquée"));
+    }
+
+    /// Fail: le numéro du pas est donné
+    #[test]
+    fn test_fail() {
+        let obstacles = obstacles(BlockedPolicy::Fail);
+        let mut walker = Walker::new(&obstacles);
+        assert_eq!(walker.walk(North).unwrap(), Move::Moved((0, 1)));
+        let err = walker.walk(East).unwrap_err().to_string();
+        assert!(err.contains("Pas 2 bloqué: la maison (1, 1)"));
+    }
+
+    /// Skip: le livreur reste sur place et continue
+    #[test]
+    fn test_skip() {
+        let obstacles = obstacles(BlockedPolicy::Skip);
+        let mut walker = Walker::new(&obstacles);
+        assert_eq!(walker.walk(East).unwrap(), Move::Skipped);
+        assert_eq!(walker.walk(SouthEast).unwrap(), Move::Moved((1, -1)));
+        assert_eq!(
+            walker.blocked,
+            vec![Blocked {
+                step: 1,
+                from: (0, 0),
+                to: (1, 0)
+            }]
+        );
+    }
+
+    /// Stop: plus aucun pas après l'obstacle
+    #[test]
+    fn test_stop() {
+        let obstacles = obstacles(BlockedPolicy::Stop);
+        let mut walker = Walker::new(&obstacles);
+        assert_eq!(walker.walk(East).unwrap(), Move::Stopped);
+        assert_eq!(walker.walk(South).unwrap(), Move::Stopped);
+        assert_eq!(walker.position, (0, 0));
+        assert_eq!(walker.blocked.len(), 1);
+    }
+
+    /// Sortir des coordonnées i32 est une erreur, pas un débordement silencieux
+    #[test]
+    fn test_overflow() {
+        let obstacles = Obstacles::default();
+        let mut walker = Walker::new(&obstacles);
+        walker.position = (i32::MAX - 1, 0);
+        assert_eq!(walker.walk(East).unwrap(), Move::Moved((i32::MAX, 0)));
+        let err = walker.walk(NorthEast).unwrap_err().to_string();
+        assert!(err.contains("Pas 2: le livreur sort du plan"));
+        assert_eq!(walker.position, (i32::MAX, 0));
+
+        walker.position = (0, i32::MIN);
+        assert!(walker.walk(South).is_err());
+    }
+}
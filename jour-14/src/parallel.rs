@@ -0,0 +1,273 @@
+use eyre::{Result, WrapErr};
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::houses::HouseSet;
+use crate::instructions::{self, Instruction};
+use crate::obstacles::{Obstacles, Walker};
+
+/// Parcours d'un morceau d'instructions, dans le repère de son point de départ
+struct Chunk {
+    /// Maisons visitées, départ du morceau compris (coordonnées modulo 2³²)
+    visited: HouseSet,
+    /// Position d'arrivée
+    displacement: (i64, i64),
+    /// Coins sud-ouest et nord-est du rectangle parcouru
+    min: (i64, i64),
+    max: (i64, i64),
+    /// Nombre de pas élémentaires du morceau
+    steps: u64,
+}
+
+/// Suit les instructions d'un morceau en partant de `(0, 0)`
+///
+/// Les positions sont suivies en i64: seul le passage au repère global dira si
+/// elles tiennent dans un i32.
+fn walk_chunk(program: &[Instruction]) -> Chunk {
+    let mut visited = HouseSet::new();
+    visited.insert((0, 0));
+    let (mut x, mut y) = (0_i64, 0_i64);
+    let (mut min, mut max) = ((0, 0), (0, 0));
+    let mut steps = 0;
+
+    for direction in instructions::steps(program) {
+        let (dx, dy) = direction.delta();
+        x += i64::from(dx);
+        y += i64::from(dy);
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+        visited.insert((x as i32, y as i32));
+        steps += 1;
+    }
+
+    Chunk {
+        visited,
+        displacement: (x, y),
+        min,
+        max,
+        steps,
+    }
+}
+
+/// Découpe le programme en au plus `chunks` morceaux contigus d'à peu près
+/// autant de pas élémentaires
+///
+/// Une instruction n'est jamais coupée: une seule grande répétition reste
+/// dans un seul morceau.
+fn split(program: &[Instruction], chunks: usize) -> Vec<&[Instruction]> {
+    let counts: Vec<u64> = program.iter().map(Instruction::step_count).collect();
+    let total = u128::from(counts.iter().fold(0, |sum: u64, &c| sum.saturating_add(c)));
+
+    let mut parts = Vec::with_capacity(chunks);
+    let (mut start, mut done) = (0, 0_u128);
+    for (index, &count) in counts.iter().enumerate() {
+        done += u128::from(count);
+        // Coupe dès que les morceaux faits ont leur part des pas
+        let due = total * (parts.len() as u128 + 1);
+        if parts.len() + 1 < chunks && done * chunks as u128 >= due {
+            parts.push(&program[start..=index]);
+            start = index + 1;
+        }
+    }
+    if start < program.len() || parts.is_empty() {
+        parts.push(&program[start..]);
+    }
+    parts
+}
+
+/// Rejoue un morceau qui sort des coordonnées i32 pour retrouver l'erreur du
+/// comptage séquentiel, numéro de pas compris
+fn overflow_error(part: &[Instruction], start: (i64, i64), step: u64) -> eyre::Report {
+    let obstacles = Obstacles::default();
+    let mut walker = Walker::new(&obstacles);
+    // Le départ est l'arrivée du morceau précédent, qui tenait dans un i32
+    walker.position = (start.0 as i32, start.1 as i32);
+    walker.step = step;
+    for direction in instructions::steps(part) {
+        if let Err(error) = walker.walk(direction) {
+            return error;
+        }
+    }
+    unreachable!("le morceau devait sortir des coordonnées i32")
+}
+
+/// Nombre de fils pour parcourir `parts` morceaux: au plus un par cœur
+/// disponible, au-delà les fils se disputeraient les mêmes cœurs
+fn workers(parts: usize) -> usize {
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+    parts.clamp(1, cores)
+}
+
+/// Parcourt les morceaux avec un groupe fixe de fils, voir `workers`
+///
+/// Chaque fil prend le prochain morceau non parcouru jusqu'à épuisement: un
+/// morceau plus long que les autres ne bloque pas tout le groupe.
+///
+/// # Errors
+/// Retourne une erreur si un fil d'exécution ne peut pas être lancé
+fn walk_parts(parts: &[&[Instruction]]) -> Result<Vec<Chunk>> {
+    let next = AtomicUsize::new(0);
+    let walked = thread::scope(|scope| -> Result<Vec<(usize, Chunk)>> {
+        let mut handles = Vec::new();
+        for worker in 0..workers(parts.len()) {
+            let next = &next;
+            let handle = thread::Builder::new()
+                .name(format!("comptage-{}", worker))
+                .spawn_scoped(scope, move || {
+                    let mut walked = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(part) = parts.get(index) else {
+                            return walked;
+                        };
+                        walked.push((index, walk_chunk(part)));
+                    }
+                })
+                .wrap_err_with(|| format!("Impossible de lancer le fil {}", worker))?;
+            handles.push(handle);
+        }
+        // Les fils déjà lancés sont attendus par `scope` même en cas d'erreur
+        Ok(handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect())
+    })?;
+
+    // Remet les morceaux dans l'ordre du programme
+    let mut ordered: Vec<Option<Chunk>> = parts.iter().map(|_| None).collect();
+    for (index, chunk) in walked {
+        ordered[index] = Some(chunk);
+    }
+    Ok(ordered
+        .into_iter()
+        .map(|chunk| chunk.expect("chaque morceau est parcouru une fois"))
+        .collect())
+}
+
+/// Compte les maisons uniques visitées en parcourant les instructions par
+/// morceaux, répartis sur au plus un fil d'exécution par cœur
+///
+/// Chaque morceau est suivi depuis l'origine de son propre repère et donne son
+/// déplacement et ses maisons visitées. La somme préfixe des déplacements
+/// donne ensuite le point de départ réel de chaque morceau, et les ensembles
+/// sont fusionnés, décalés d'autant, dans l'ensemble global. Le résultat est
+/// celui de `count_unique_houses` sans obstacles, erreurs comprises.
+///
+/// Les obstacles ne sont pas gérés: avec la politique `Skip`, le déplacement
+/// d'un morceau dépend de son point de départ.
+///
+/// # Complexité algorithmique
+/// * Temps: O(n / p + t) où n est le nombre de pas élémentaires, p le nombre
+///   de fils et t le nombre total de tuiles de 64x64 maisons des morceaux
+///   (la fusion est séquentielle, mais ne dépend pas du nombre de maisons)
+/// * Espace: O(t)
+///
+/// # Errors
+/// Retourne une erreur si `chunks` vaut 0, si un fil d'exécution ne peut pas
+/// être lancé, ou si le livreur sort des coordonnées représentables par un `i32`
+pub fn count_unique_houses(program: &[Instruction], chunks: usize) -> Result<usize> {
+    if chunks == 0 {
+        eyre::bail!("Il faut au moins un morceau");
+    }
+
+    let parts = split(program, chunks);
+    let walked = walk_parts(&parts)?;
+
+    // Somme préfixe des déplacements: chaque morceau part de l'arrivée du précédent
+    let mut visited = HouseSet::new();
+    let (mut offset, mut step) = ((0_i64, 0_i64), 0);
+    for (part, chunk) in parts.iter().zip(&walked) {
+        let fits = |value: i64| i32::try_from(value).is_ok();
+        if !(fits(offset.0 + chunk.min.0)
+            && fits(offset.1 + chunk.min.1)
+            && fits(offset.0 + chunk.max.0)
+            && fits(offset.1 + chunk.max.1))
+        {
+            return Err(overflow_error(part, offset, step));
+        }
+
+        visited.merge_shifted(&chunk.visited, (offset.0 as i32, offset.1 as i32));
+        offset = (
+            offset.0 + chunk.displacement.0,
+            offset.1 + chunk.displacement.1,
+        );
+        step += chunk.steps;
+    }
+
+    Ok(visited.len())
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{Syntax, parse};
+    use crate::xorshift::XorShift;
+
+    fn sequential(program: &[Instruction]) -> Result<usize> {
+        crate::count_unique_houses(program, &Obstacles::default())
+    }
+
+    /// Nombre de pas sans développer les répétitions
+    #[test]
+    fn test_step_count() {
+        let program = parse("N3 (E W2)4 ((S)2)3", Syntax::Extended).unwrap();
+        let counts: Vec<u64> = program.iter().map(Instruction::step_count).collect();
+        assert_eq!(counts, vec![3, 12, 6]);
+    }
+
+    /// Morceaux contigus, équilibrés en pas et jamais vides sauf programme vide
+    #[test]
+    fn test_split() {
+        let program = parse("N9 E S E S E S E S E", Syntax::Extended).unwrap();
+        let parts = split(&program, 2);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].len(), 1);
+        assert_eq!(parts[1].len(), 9);
+
+        assert_eq!(split(&program, 50).len(), 10);
+        assert_eq!(split(&[], 4), vec![&[] as &[Instruction]]);
+    }
+
+    /// Même résultat que le comptage séquentiel, quel que soit le découpage
+    #[test]
+    fn test_matches_sequential() {
+        let program = XorShift::default().program(20_000);
+        let expected = sequential(&program).unwrap();
+        for chunks in [1, 2, 3, 7, 64, 100_000] {
+            assert_eq!(count_unique_houses(&program, chunks).unwrap(), expected);
+        }
+        // Cent mille morceaux demandés, pas plus d'un fil par cœur
+        let cores = thread::available_parallelism().map_or(1, usize::from);
+        assert_eq!(workers(100_000), cores);
+        assert_eq!(workers(1), 1);
+
+        let program = parse("(NE3 S)500 W1000 (N2 (SW)3)200", Syntax::Extended).unwrap();
+        assert_eq!(
+            count_unique_houses(&program, 3).unwrap(),
+            sequential(&program).unwrap()
+        );
+        assert_eq!(count_unique_houses(&[], 4).unwrap(), 1);
+        assert!(count_unique_houses(&program, 0).is_err());
+    }
+
+    /// Le morceau qui sort des coordonnées i32 est rejoué depuis son vrai
+    /// départ: l'erreur est celle du comptage séquentiel, numéro de pas compris
+    #[test]
+    fn test_overflow_error() {
+        let part = parse("N E3", Syntax::Extended).unwrap();
+        let error = overflow_error(&part, (i64::from(i32::MAX) - 2, 5), 40).to_string();
+        assert_eq!(
+            error,
+            format!(
+                "Pas 44: le livreur sort du plan des coordonnées représentables (livreur en ({}, 6))",
+                i32::MAX
+            )
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Stylize};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use eyre::Result;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::heatmap::{heat_color, rgb, shade};
+use crate::instructions::{self, Instruction, Steps};
+use crate::obstacles::{Move, Obstacles, Walker};
+use crate::visits::House;
+
+/// Durée minimale d'une image: au-delà de 60 pas par seconde, plusieurs pas
+/// sont joués par image
+const MIN_FRAME: Duration = Duration::from_micros(16_667);
+
+/// Vitesses extrêmes accessibles avec les touches + et -
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 1_000_000.0;
+
+/// Options de la relecture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    /// Pas joués par seconde
+    pub speed: f64,
+    /// Démarre en pause
+    pub paused: bool,
+    /// Couleurs ANSI, sinon nuances `░▒▓█`
+    pub color: bool,
+}
+
+/// État de la tournée rejouée pas à pas
+pub struct Replay<'a> {
+    steps: Steps<'a>,
+    walker: Walker<'a>,
+    obstacles: &'a Obstacles,
+    /// Position actuelle du livreur
+    pub position: House,
+    /// Nombre de pas déjà joués, pas bloqués compris
+    pub step: u64,
+    /// Passages dans chaque maison jusqu'ici, départ compris
+    pub counts: HashMap<House, u32>,
+    /// Nombre de passages de la maison la plus visitée jusqu'ici
+    pub max_count: u32,
+    /// Toutes les instructions ont été jouées, ou un obstacle a arrêté la tournée
+    pub finished: bool,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(program: &'a [Instruction], obstacles: &'a Obstacles) -> Self {
+        Replay {
+            steps: instructions::steps(program),
+            walker: Walker::new(obstacles),
+            obstacles,
+            position: (0, 0),
+            step: 0,
+            counts: HashMap::from([((0, 0), 1)]),
+            max_count: 1,
+            finished: false,
+        }
+    }
+
+    /// Joue le pas suivant, renvoie `false` s'il n'y en a plus
+    ///
+    /// # Errors
+    /// Retourne une erreur si le pas mène sur un obstacle avec la politique `Fail`
+    pub fn advance(&mut self) -> Result<bool> {
+        let Some(direction) = self.steps.next().filter(|_| !self.finished) else {
+            self.finished = true;
+            return Ok(false);
+        };
+
+        let moved = self.walker.walk(direction)?;
+        self.step = self.walker.step;
+        match moved {
+            Move::Moved(house) => {
+                self.position = house;
+                let count = self.counts.entry(house).or_insert(0);
+                *count += 1;
+                self.max_count = self.max_count.max(*count);
+            }
+            Move::Skipped => {}
+            Move::Stopped => self.finished = true,
+        }
+        Ok(true)
+    }
+
+    /// Nombre de pas bloqués par des obstacles jusqu'ici
+    pub fn blocked(&self) -> usize {
+        self.walker.blocked.len()
+    }
+}
+
+/// Fenêtre sur le quartier, une maison par caractère
+///
+/// La fenêtre ne bouge que lorsque le livreur s'approche à moins d'un quart
+/// de sa taille d'un bord, pour éviter que toute la carte défile à chaque pas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// Abscisse de la colonne de gauche
+    pub left: i64,
+    /// Ordonnée de la ligne du haut (nord)
+    pub top: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Fenêtre de `width` x `height` maisons centrée sur `house`
+    pub fn centered(house: House, width: usize, height: usize) -> Self {
+        Viewport {
+            left: i64::from(house.0) - width as i64 / 2,
+            top: i64::from(house.1) + height as i64 / 2,
+            width,
+            height,
+        }
+    }
+
+    /// Décale la fenêtre juste assez pour garder `house` hors des marges
+    pub fn follow(&mut self, house: House) {
+        let (x, y) = (i64::from(house.0), i64::from(house.1));
+        let margin_x = (self.width / 4) as i64;
+        let margin_y = (self.height / 4) as i64;
+        let right = self.left + self.width as i64 - 1;
+        let bottom = self.top - self.height as i64 + 1;
+
+        if x < self.left + margin_x {
+            self.left = x - margin_x;
+        } else if x > right - margin_x {
+            self.left = x + margin_x - self.width as i64 + 1;
+        }
+        if y > self.top - margin_y {
+            self.top = y + margin_y;
+        } else if y < bottom + margin_y {
+            self.top = y - margin_y + self.height as i64 - 1;
+        }
+    }
+
+    /// Change la taille de la fenêtre (terminal redimensionné) en la recentrant
+    fn resize(&mut self, house: House, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height) {
+            *self = Viewport::centered(house, width, height);
+        }
+    }
+}
+
+/// Lignes de la carte visible: livreur `@`, départ `H`, obstacles `#`,
+/// maisons nuancées selon leur nombre de passages
+pub fn render_frame(replay: &Replay, viewport: &Viewport, color: bool) -> Vec<String> {
+    (0..viewport.height)
+        .map(|row| {
+            let y = viewport.top - row as i64;
+            (0..viewport.width)
+                .map(|column| {
+                    let x = viewport.left + column as i64;
+                    let house = (i32::try_from(x), i32::try_from(y));
+                    let house = match house {
+                        (Ok(x), Ok(y)) => (x, y),
+                        _ => return " ".to_string(),
+                    };
+
+                    let count = replay.counts.get(&house).copied().unwrap_or(0);
+                    let label = if house == replay.position {
+                        Some('@')
+                    } else if house == (0, 0) {
+                        Some('H')
+                    } else if replay.obstacles.map.is_blocked(house) {
+                        Some('#')
+                    } else {
+                        None
+                    };
+
+                    match (label, color) {
+                        (Some(label), true) => {
+                            let styled = label.with(Color::White).bold();
+                            if count > 0 {
+                                styled
+                                    .on(rgb(heat_color(count, replay.max_count)))
+                                    .to_string()
+                            } else {
+                                styled.to_string()
+                            }
+                        }
+                        (Some(label), false) => label.to_string(),
+                        (None, _) if count == 0 => " ".to_string(),
+                        (None, true) => '█'
+                            .with(rgb(heat_color(count, replay.max_count)))
+                            .to_string(),
+                        (None, false) => shade(count, replay.max_count).to_string(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Ligne d'état affichée sous la carte
+fn status_line(replay: &Replay, speed: f64, paused: bool) -> String {
+    let state = if replay.finished {
+        "terminé"
+    } else if paused {
+        "pause"
+    } else {
+        "lecture"
+    };
+    format!(
+        "pas {}  ({}, {})  {} maisons  {} bloqués  {} pas/s  [{}]  espace: pause  n/→: pas suivant  +/-: vitesse  q: quitter",
+        replay.step,
+        replay.position.0,
+        replay.position.1,
+        replay.counts.len(),
+        replay.blocked(),
+        speed,
+        state
+    )
+}
+
+/// Terminal en mode brut sur l'écran alternatif, restauré à la destruction
+///
+/// La restauration passe par `Drop` pour avoir lieu aussi quand l'animation
+/// échoue ou panique.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        // Créé dès le mode brut actif: un échec de la suite le restaure aussi
+        let guard = RawTerminal;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        // Rien de mieux à faire d'une erreur ici: on restaure ce qu'on peut
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Rejoue la tournée dans le terminal jusqu'à ce que l'utilisateur quitte
+///
+/// Le terminal passe en mode brut sur un écran alternatif; il est restauré
+/// même si l'animation échoue ou panique.
+pub fn run(program: &[Instruction], obstacles: &Obstacles, options: &ReplayOptions) -> Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    animate(&mut io::stdout(), program, obstacles, options)
+}
+
+/// Taille de la carte pour le terminal actuel (une ligne pour l'état)
+fn map_size() -> (usize, usize) {
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    (
+        usize::from(columns).max(1),
+        usize::from(rows).saturating_sub(1).max(1),
+    )
+}
+
+fn animate(
+    out: &mut impl Write,
+    program: &[Instruction],
+    obstacles: &Obstacles,
+    options: &ReplayOptions,
+) -> Result<()> {
+    let mut replay = Replay::new(program, obstacles);
+    let (width, height) = map_size();
+    let mut viewport = Viewport::centered(replay.position, width, height);
+    let mut speed = options.speed.clamp(MIN_SPEED, MAX_SPEED);
+    let mut paused = options.paused;
+
+    // Pas dus depuis la dernière image: la vitesse est tenue d'après le temps
+    // écoulé, quel que soit le temps de dessin ou de réveil du terminal
+    let mut last = Instant::now();
+    let mut due = 0.0;
+
+    loop {
+        // Dessine l'image courante
+        let (width, height) = map_size();
+        viewport.resize(replay.position, width, height);
+        viewport.follow(replay.position);
+        for (row, line) in render_frame(&replay, &viewport, options.color)
+            .iter()
+            .enumerate()
+        {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            write!(out, "{}", line)?;
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, height as u16),
+            terminal::Clear(ClearType::CurrentLine)
+        )?;
+        write!(out, "{}", status_line(&replay, speed, paused))?;
+        out.flush()?;
+
+        // Attend une touche ou l'image suivante
+        let frame = Duration::from_secs_f64(1.0 / speed).max(MIN_FRAME);
+        // En pause, on attend simplement la prochaine touche
+        let waiting = paused || replay.finished;
+        if waiting || event::poll(frame)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(());
+                    }
+                    KeyCode::Char(' ') => {
+                        paused = !paused;
+                        last = Instant::now();
+                    }
+                    KeyCode::Char('n') | KeyCode::Right => {
+                        paused = true;
+                        replay.advance()?;
+                    }
+                    KeyCode::Char('+') => speed = (speed * 2.0).min(MAX_SPEED),
+                    KeyCode::Char('-') => speed = (speed / 2.0).max(MIN_SPEED),
+                    _ => {}
+                },
+                Event::Resize(..) => execute!(out, terminal::Clear(ClearType::All))?,
+                _ => {}
+            }
+            continue;
+        }
+
+        // Joue les pas dus depuis la dernière image
+        let now = Instant::now();
+        due += speed * now.duration_since(last).as_secs_f64();
+        last = now;
+        while due >= 1.0 {
+            due -= 1.0;
+            if !replay.advance()? {
+                due = 0.0;
+                break;
+            }
+        }
+    }
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{Syntax, parse};
+    use crate::obstacles::{BlockedPolicy, ObstacleMap};
+
+    /// Le compteur de passages suit les pas un par un
+    #[test]
+    fn test_replay_advance() {
+        let program = parse("NS", Syntax::Plain).unwrap();
+        let obstacles = Obstacles::default();
+        let mut replay = Replay::new(&program, &obstacles);
+        assert!(replay.advance().unwrap());
+        assert_eq!((replay.position, replay.step), ((0, 1), 1));
+        assert!(replay.advance().unwrap());
+        assert_eq!(replay.counts[&(0, 0)], 2);
+        assert_eq!(replay.max_count, 2);
+        assert!(!replay.advance().unwrap());
+        assert!(replay.finished);
+    }
+
+    /// La fenêtre ne se décale qu'en entrant dans la marge
+    #[test]
+    fn test_viewport_follow() {
+        let mut viewport = Viewport::centered((0, 0), 8, 4);
+        assert_eq!((viewport.left, viewport.top), (-4, 2));
+
+        // (1, 0) reste hors des marges de 2 colonnes et 1 ligne
+        viewport.follow((1, 0));
+        assert_eq!((viewport.left, viewport.top), (-4, 2));
+
+        // (2, 0) entre dans la marge de droite: la fenêtre se décale d'une colonne
+        viewport.follow((2, 0));
+        assert_eq!((viewport.left, viewport.top), (-3, 2));
+
+        // Très loin au sud-ouest, la fenêtre rattrape le livreur
+        viewport.follow((-100, -50));
+        assert_eq!((viewport.left, viewport.top), (-102, -48));
+    }
+
+    /// Rendu sans couleur: livreur, départ, obstacles et maisons nuancées
+    /// NNSSE: (0,1) visitée 2 fois, (0,2) une fois, le livreur finit en (1,0)
+    #[test]
+    fn test_render_frame_plain() {
+        let program = parse("NNSSE", Syntax::Plain).unwrap();
+        let obstacles = Obstacles {
+            map: ObstacleMap::parse("#..\n.H.\n").unwrap(),
+            policy: BlockedPolicy::Fail,
+        };
+        let mut replay = Replay::new(&program, &obstacles);
+        while replay.advance().unwrap() {}
+
+        let viewport = Viewport {
+            left: -1,
+            top: 2,
+            width: 3,
+            height: 3,
+        };
+        let frame = render_frame(&replay, &viewport, false);
+        assert_eq!(frame, vec![" ░ ", "#█ ", " H@"]);
+    }
+
+    /// Un obstacle avec la politique Stop termine la relecture
+    #[test]
+    fn test_replay_stop() {
+        let program = parse("EN", Syntax::Plain).unwrap();
+        let obstacles = Obstacles {
+            map: ObstacleMap::parse("H#\n").unwrap(),
+            policy: BlockedPolicy::Stop,
+        };
+        let mut replay = Replay::new(&program, &obstacles);
+        assert!(replay.advance().unwrap());
+        assert!(replay.finished);
+        assert_eq!(replay.blocked(), 1);
+        assert!(!replay.advance().unwrap());
+        assert_eq!(replay.position, (0, 0));
+    }
+}
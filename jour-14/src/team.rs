@@ -0,0 +1,205 @@
+use eyre::{Result, WrapErr};
+use std::collections::{HashMap, HashSet};
+
+use crate::instructions::{self, Instruction};
+use crate::obstacles::{Move, Obstacles, Walker};
+
+/// Bilan d'une tournée effectuée par une équipe de livreurs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamReport {
+    /// Nombre de maisons uniques visitées par l'ensemble de l'équipe
+    pub unique_houses: usize,
+    /// Nombre de maisons uniques visitées par chaque livreur
+    pub per_courier: Vec<usize>,
+    /// Nombre de maisons visitées par au moins deux livreurs
+    pub shared_houses: usize,
+    /// Maisons communes à chaque paire de livreurs `(i, j, maisons)`, avec `i < j`
+    pub pairwise_overlap: Vec<(usize, usize, usize)>,
+    /// Nombre de pas qui ont buté sur un obstacle, tous livreurs confondus
+    pub blocked: usize,
+}
+
+/// Fait suivre les instructions à `couriers` livreurs qui les prennent à tour de rôle.
+///
+/// Le livreur `i` exécute les pas élémentaires d'indice `i`, `i + K`, `i + 2K`...
+/// (`N3` compte pour trois pas, éventuellement faits par trois livreurs).
+/// Tous les livreurs partent de la maison d'origine `(0, 0)`, qui compte donc
+/// pour chacun d'eux. Avec un seul livreur, `unique_houses` est égal au
+/// résultat de `count_unique_houses`.
+///
+/// # Complexité algorithmique
+/// * Temps: O(n + K² · m) où n est la longueur des instructions et m le nombre
+///   de maisons visitées par un livreur (le terme K² vient des recouvrements
+///   entre paires)
+/// * Espace: O(n + K)
+///
+/// Chaque livreur respecte les obstacles pour son compte: avec la politique
+/// `Stop`, un livreur arrêté laisse passer ses pas sans bouger, les autres
+/// continuent.
+///
+/// # Errors
+/// Retourne une erreur si `couriers` vaut 0, ou si un pas mène sur un
+/// obstacle avec la politique `Fail`
+pub fn deliver(
+    program: &[Instruction],
+    couriers: usize,
+    obstacles: &Obstacles,
+) -> Result<TeamReport> {
+    if couriers == 0 {
+        eyre::bail!("Il faut au moins un livreur");
+    }
+
+    // Position courante et maisons visitées de chaque livreur
+    let mut walkers: Vec<Walker> = (0..couriers).map(|_| Walker::new(obstacles)).collect();
+    let mut visited: Vec<HashSet<(i32, i32)>> =
+        (0..couriers).map(|_| HashSet::from([(0, 0)])).collect();
+
+    // Distribue les pas à tour de rôle
+    for (index, direction) in instructions::steps(program).enumerate() {
+        let courier = index % couriers;
+        if let Move::Moved(house) = walkers[courier].walk(direction).wrap_err_with(|| {
+            format!(
+                "Livreur {} au pas {} des instructions",
+                courier + 1,
+                index + 1
+            )
+        })? {
+            visited[courier].insert(house);
+        }
+    }
+
+    // Nombre de livreurs passés par chaque maison
+    let mut visitors: HashMap<(i32, i32), usize> = HashMap::new();
+    for houses in &visited {
+        for &house in houses {
+            *visitors.entry(house).or_default() += 1;
+        }
+    }
+
+    // Recouvrement de chaque paire de livreurs, en parcourant le plus petit ensemble
+    let mut pairwise_overlap = Vec::with_capacity(couriers * (couriers - 1) / 2);
+    for i in 0..couriers {
+        for j in i + 1..couriers {
+            let (small, large) = if visited[i].len() <= visited[j].len() {
+                (&visited[i], &visited[j])
+            } else {
+                (&visited[j], &visited[i])
+            };
+            let common = small.iter().filter(|house| large.contains(house)).count();
+            pairwise_overlap.push((i, j, common));
+        }
+    }
+
+    Ok(TeamReport {
+        unique_houses: visitors.len(),
+        per_courier: visited.iter().map(HashSet::len).collect(),
+        shared_houses: visitors.values().filter(|&&count| count > 1).count(),
+        pairwise_overlap,
+        blocked: walkers.iter().map(|walker| walker.blocked.len()).sum(),
+    })
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_unique_houses;
+    use crate::instructions::Syntax;
+
+    /// Analyse les instructions au format historique puis les distribue
+    fn deliver_plain(text: &str, couriers: usize) -> Result<TeamReport> {
+        deliver(
+            &instructions::parse(text, Syntax::Plain)?,
+            couriers,
+            &Obstacles::default(),
+        )
+    }
+
+    /// Avec un seul livreur, le résultat est celui de `count_unique_houses`
+    #[test]
+    fn test_single_courier_matches() {
+        for instructions in ["", "N", "NNESESW", "NNSS", "NESWNESW"] {
+            let report = deliver_plain(instructions, 1).unwrap();
+            let expected = count_unique_houses(
+                &instructions::parse(instructions, Syntax::Plain).unwrap(),
+                &Obstacles::default(),
+            )
+            .unwrap();
+            assert_eq!(report.unique_houses, expected);
+            assert_eq!(report.per_courier, vec![expected]);
+            assert_eq!(report.shared_houses, 0);
+            assert!(report.pairwise_overlap.is_empty());
+        }
+    }
+
+    /// Deux livreurs qui s'éloignent dans des directions opposées
+    /// NSNS: le livreur 1 fait N, N et le livreur 2 fait S, S
+    /// Seule la maison de départ est commune
+    #[test]
+    fn test_two_couriers_opposite() {
+        let report = deliver_plain("NSNS", 2).unwrap();
+        assert_eq!(report.unique_houses, 5);
+        assert_eq!(report.per_courier, vec![3, 3]);
+        assert_eq!(report.shared_houses, 1);
+        assert_eq!(report.pairwise_overlap, vec![(0, 1, 1)]);
+    }
+
+    /// Deux livreurs qui suivent le même chemin
+    /// NNEE: les deux livreurs font N puis E
+    #[test]
+    fn test_two_couriers_same_path() {
+        let report = deliver_plain("NNEE", 2).unwrap();
+        assert_eq!(report.unique_houses, 3);
+        assert_eq!(report.per_courier, vec![3, 3]);
+        assert_eq!(report.shared_houses, 3);
+    }
+
+    /// Plus de livreurs que d'instructions: les livreurs en trop restent chez eux
+    #[test]
+    fn test_more_couriers_than_instructions() {
+        let report = deliver_plain("NE", 3).unwrap();
+        assert_eq!(report.unique_houses, 3);
+        assert_eq!(report.per_courier, vec![2, 2, 1]);
+        assert_eq!(
+            report.pairwise_overlap,
+            vec![(0, 1, 1), (0, 2, 1), (1, 2, 1)]
+        );
+    }
+
+    /// Une équipe vide est refusée
+    #[test]
+    fn test_invalid_team() {
+        assert!(deliver_plain("N", 0).is_err());
+    }
+
+    /// Les pas d'un compte sont distribués un par un
+    /// N2 S2: le livreur 1 fait N puis S, le livreur 2 fait N puis S
+    #[test]
+    fn test_counts_are_distributed() {
+        let program = instructions::parse("N2 S2", Syntax::Extended).unwrap();
+        let report = deliver(&program, 2, &Obstacles::default()).unwrap();
+        assert_eq!(report.per_courier, vec![2, 2]);
+        assert_eq!(report.shared_houses, 2);
+    }
+
+    /// Les obstacles s'appliquent à chaque livreur
+    /// Mur en (0, 1): le livreur 1 (N, N) est bloqué deux fois, le livreur 2 (E, E) passe
+    #[test]
+    fn test_team_obstacles() {
+        let program = instructions::parse("NENE", Syntax::Plain).unwrap();
+        let obstacles = Obstacles {
+            map: crate::obstacles::ObstacleMap::parse("#.\nH.\n").unwrap(),
+            policy: crate::obstacles::BlockedPolicy::Skip,
+        };
+        let report = deliver(&program, 2, &obstacles).unwrap();
+        assert_eq!(report.per_courier, vec![1, 3]);
+        assert_eq!(report.blocked, 2);
+
+        let obstacles = Obstacles {
+            policy: crate::obstacles::BlockedPolicy::Fail,
+            ..obstacles
+        };
+        let err = deliver(&program, 2, &obstacles).unwrap_err();
+        assert!(format!("{:#}", err).contains("Livreur 1 au pas 1 des instructions: Pas 1 bloqué"));
+    }
+}
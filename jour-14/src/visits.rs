@@ -0,0 +1,221 @@
+use eyre::Result;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::instructions::{self, Instruction};
+use crate::obstacles::{Blocked, Move, Obstacles, Walker};
+
+/// Position d'une maison `(x, y)`, y augmentant vers le nord
+pub type House = (i32, i32);
+
+/// Première maison où le livreur repasse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Revisit {
+    pub house: House,
+    /// Numéro du pas qui y ramène le livreur (le départ est le pas 0)
+    pub step: u64,
+}
+
+/// Nombre de passages par maison le long d'une tournée
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Visits {
+    /// Nombre de passages dans chaque maison visitée, départ compris
+    pub counts: HashMap<House, u32>,
+    /// Première maison visitée deux fois, s'il y en a une
+    pub first_revisit: Option<Revisit>,
+    /// Position du livreur à la fin des instructions
+    pub final_position: House,
+    /// Pas qui ont buté sur un obstacle
+    pub blocked: Vec<Blocked>,
+    /// La tournée s'est arrêtée devant un obstacle avant la fin des instructions
+    pub stopped: bool,
+}
+
+/// Suit les instructions en comptant les passages dans chaque maison
+///
+/// Un pas ignoré à cause d'un obstacle ne compte pas de passage, le livreur
+/// n'ayant pas bougé.
+///
+/// # Complexité algorithmique
+/// * Temps: O(n) où n est le nombre de pas élémentaires (répétitions développées)
+///   - On parcourt chaque pas une fois
+///   - Les mises à jour de la HashMap sont O(1) en moyenne
+/// * Espace: O(n) dans le pire cas
+///   - La HashMap peut contenir jusqu'à n+1 positions si tous les déplacements
+///     mènent à de nouvelles maisons
+///
+/// # Errors
+/// Retourne une erreur si un pas mène sur un obstacle avec la politique `Fail`
+pub fn record(program: &[Instruction], obstacles: &Obstacles) -> Result<Visits> {
+    // HashMap associant à chaque position (x, y) son nombre de passages
+    let mut counts = HashMap::new();
+    let mut first_revisit = None;
+
+    // Position initiale du livreur, comptée comme un premier passage
+    let mut walker = Walker::new(obstacles);
+    counts.insert(walker.position, 1);
+
+    // Parcourt chaque pas élémentaire
+    for direction in instructions::steps(program) {
+        // Met à jour la position selon la direction (y augmente vers le nord)
+        let house = match walker.walk(direction)? {
+            Move::Moved(house) => house,
+            Move::Skipped => continue,
+            Move::Stopped => break,
+        };
+
+        // Incrémente le compteur de la maison, en notant le premier retour
+        let count = counts.entry(house).or_insert(0);
+        *count += 1;
+        if *count == 2 && first_revisit.is_none() {
+            first_revisit = Some(Revisit {
+                house,
+                step: walker.step,
+            });
+        }
+    }
+
+    Ok(Visits {
+        counts,
+        first_revisit,
+        final_position: walker.position,
+        blocked: walker.blocked,
+        stopped: walker.stopped,
+    })
+}
+
+impl Visits {
+    /// Nombre de maisons uniques visitées (position de départ incluse)
+    pub fn unique_houses(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Les `n` maisons les plus visitées, par nombre de passages décroissant
+    /// puis par position pour un ordre stable
+    pub fn most_visited(&self, n: usize) -> Vec<(House, u32)> {
+        let mut houses: Vec<(House, u32)> = self.counts.iter().map(|(&h, &c)| (h, c)).collect();
+        houses.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        houses.truncate(n);
+        houses
+    }
+
+    /// Nombre de maisons visitées exactement k fois, pour chaque k
+    pub fn histogram(&self) -> BTreeMap<u32, usize> {
+        let mut histogram = BTreeMap::new();
+        for &count in self.counts.values() {
+            *histogram.entry(count).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Maison la plus éloignée du départ en distance de Manhattan |x| + |y|
+    pub fn furthest_manhattan(&self) -> (House, u64) {
+        self.counts
+            .keys()
+            .map(|&(x, y)| ((x, y), x.unsigned_abs() as u64 + y.unsigned_abs() as u64))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .unwrap_or(((0, 0), 0))
+    }
+
+    /// Maison la plus éloignée du départ à vol d'oiseau √(x² + y²)
+    pub fn furthest_euclidean(&self) -> (House, f64) {
+        // Compare les carrés en entiers pour éviter les égalités approximatives
+        let ((x, y), squared) = self
+            .counts
+            .keys()
+            .map(|&(x, y)| {
+                let (x2, y2) = (i64::from(x), i64::from(y));
+                ((x, y), (x2 * x2 + y2 * y2) as u128)
+            })
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .unwrap_or(((0, 0), 0));
+        ((x, y), (squared as f64).sqrt())
+    }
+}
+
+/// Module de tests unitaires
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{Syntax, parse};
+    use crate::obstacles::{BlockedPolicy, ObstacleMap};
+
+    fn visits(text: &str, syntax: Syntax) -> Visits {
+        record(&parse(text, syntax).unwrap(), &Obstacles::default()).unwrap()
+    }
+
+    /// NNSS: (0,0) -> (0,1) -> (0,2) -> (0,1) -> (0,0)
+    /// (0,1) est la première maison revisitée, au pas 3
+    #[test]
+    fn test_counts_and_first_revisit() {
+        let visits = visits("NNSS", Syntax::Plain);
+        assert_eq!(visits.unique_houses(), 3);
+        assert_eq!(visits.counts[&(0, 0)], 2);
+        assert_eq!(visits.counts[&(0, 1)], 2);
+        assert_eq!(visits.counts[&(0, 2)], 1);
+        assert_eq!(
+            visits.first_revisit,
+            Some(Revisit {
+                house: (0, 1),
+                step: 3
+            })
+        );
+        assert_eq!(visits.final_position, (0, 0));
+    }
+
+    /// Sans retour en arrière, aucune maison n'est revisitée
+    #[test]
+    fn test_no_revisit() {
+        let visits = visits("NNESESW", Syntax::Plain);
+        assert_eq!(visits.first_revisit, None);
+        assert_eq!(visits.histogram(), BTreeMap::from([(1, 8)]));
+    }
+
+    /// Classement et histogramme
+    /// (NS)3: (0,0) visitée 4 fois, (0,1) visitée 3 fois
+    #[test]
+    fn test_most_visited_and_histogram() {
+        let visits = visits("(NS)3 E", Syntax::Extended);
+        assert_eq!(visits.most_visited(2), vec![((0, 0), 4), ((0, 1), 3)]);
+        assert_eq!(visits.histogram(), BTreeMap::from([(1, 1), (3, 1), (4, 1)]));
+    }
+
+    /// Manhattan et vol d'oiseau ne désignent pas toujours la même maison
+    /// (3,0) est à 3 en Manhattan et en ligne droite, (2,2) à 4 et √8 ≈ 2.83
+    #[test]
+    fn test_furthest() {
+        let visits = visits("E3 W3 NE2", Syntax::Extended);
+        assert_eq!(visits.furthest_manhattan(), ((2, 2), 4));
+        assert_eq!(visits.furthest_euclidean(), ((3, 0), 3.0));
+    }
+
+    /// Les pas ignorés ne comptent pas de passage et décalent le premier retour
+    /// Mur en (1, 0): N S ramène au départ au pas 2, E (pas 3) est ignoré,
+    /// W mène en (-1, 0) et E revient au départ
+    #[test]
+    fn test_record_with_obstacles() {
+        let program = parse("NSEWE", Syntax::Plain).unwrap();
+        let obstacles = Obstacles {
+            map: ObstacleMap::parse("H#\n").unwrap(),
+            policy: BlockedPolicy::Skip,
+        };
+        let visits = record(&program, &obstacles).unwrap();
+        assert_eq!(visits.counts[&(0, 0)], 3);
+        assert_eq!(visits.blocked.len(), 1);
+        assert_eq!(visits.blocked[0].step, 3);
+        assert_eq!(
+            visits.first_revisit,
+            Some(Revisit {
+                house: (0, 0),
+                step: 2
+            })
+        );
+
+        let obstacles = Obstacles {
+            policy: BlockedPolicy::Stop,
+            ..obstacles
+        };
+        let visits = record(&program, &obstacles).unwrap();
+        assert!(visits.stopped);
+        assert_eq!(visits.unique_houses(), 2);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::instructions::{Direction, Instruction};
+
+/// Générateur pseudo-aléatoire xorshift64, pour les tests et les benchmarks
+///
+/// Les suites sont reproductibles d'une exécution à l'autre et ne dépendent
+/// d'aucune bibliothèque. Ce n'est pas un générateur cryptographique.
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// Graine par défaut des tests et des benchmarks
+    pub const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+    /// Générateur partant de `seed` (une graine nulle donnerait toujours 0,
+    /// elle est remplacée par la graine par défaut)
+    pub fn new(seed: u64) -> Self {
+        XorShift(if seed == 0 { Self::SEED } else { seed })
+    }
+
+    /// Nombre suivant de la suite, jamais nul
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Direction tirée parmi les huit, diagonales comprises
+    pub fn direction(&mut self) -> Direction {
+        use Direction::*;
+        [
+            North, South, East, West, NorthEast, NorthWest, SouthEast, SouthWest,
+        ][(self.next_u64() >> 61) as usize]
+    }
+
+    /// Programme de `len` instructions `Step` de 1 à 4 pas, diagonales comprises
+    pub fn program(&mut self, len: usize) -> Vec<Instruction> {
+        (0..len)
+            .map(|_| Instruction::Step {
+                direction: self.direction(),
+                count: (self.next_u64() >> 62) as u32 + 1,
+            })
+            .collect()
+    }
+}
+
+impl Default for XorShift {
+    fn default() -> Self {
+        Self::new(Self::SEED)
+    }
+}